@@ -0,0 +1,482 @@
+use std::{cmp::Ordering, fmt::Display};
+
+use crate::{ControlVariants, Number, Position, TokenTypes, TokenVariant, WordVariants};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Int,
+    Float,
+    Str,
+    Bool,
+    Void,
+    Record(String),
+    Array(Box<Type>),
+}
+impl Type {
+    pub fn from_token(token: &TokenTypes) -> Option<Type> {
+        match token {
+            TokenTypes::Word(WordVariants::Int) => Some(Self::Int),
+            TokenTypes::Word(WordVariants::Float) => Some(Self::Float),
+            TokenTypes::Word(WordVariants::Str) => Some(Self::Str),
+            TokenTypes::Word(WordVariants::Bool) => Some(Self::Bool),
+            _ => None,
+        }
+    }
+
+    /// Whether a value of `other` type can be used where `self` is expected.
+    /// The empty array literal has the type `void[]` and fits every array.
+    pub fn accepts(&self, other: &Type) -> bool {
+        match (self, other) {
+            (Self::Array(_), Self::Array(element)) if **element == Self::Void => true,
+            (Self::Array(expected), Self::Array(element)) => expected.accepts(element),
+            (expected, other) => expected == other,
+        }
+    }
+}
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int => write!(f, "int"),
+            Self::Float => write!(f, "float"),
+            Self::Str => write!(f, "str"),
+            Self::Bool => write!(f, "bool"),
+            Self::Void => write!(f, "void"),
+            Self::Record(name) => write!(f, "{}", name),
+            // the element type of `[]` is unknown, not void
+            Self::Array(element) if **element == Self::Void => write!(f, "empty array"),
+            Self::Array(element) => write!(f, "{}[]", element),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+}
+impl Literal {
+    /// The literal of `token`, an int literal of 2^63 is only one as part of
+    /// `-2^63`.
+    pub fn from_token(token: &TokenTypes) -> Option<Literal> {
+        match token {
+            TokenTypes::Number(num) => match num.value() {
+                Number::Int(int) => i64::try_from(int).ok().map(Self::Int),
+                Number::Float(float) => Some(Self::Float(float)),
+            },
+            TokenTypes::String(str) => Some(Self::Str(str.value().to_string())),
+            TokenTypes::Boolean(bool) => Some(Self::Bool(bool.value())),
+            _ => None,
+        }
+    }
+
+    pub fn value_type(&self) -> Type {
+        match self {
+            Self::Int(_) => Type::Int,
+            Self::Float(_) => Type::Float,
+            Self::Str(_) => Type::Str,
+            Self::Bool(_) => Type::Bool,
+        }
+    }
+}
+impl Display for Literal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int(int) => write!(f, "{}", int),
+            Self::Float(float) => write!(f, "{:?}", float),
+            Self::Str(str) => write!(f, "\"{}\"", str),
+            Self::Bool(bool) => write!(f, "{}", bool),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////
+//                                                           ///
+//                     Arithmetic                            ///
+//                                                           ///
+////////////////////////////////////////////////////////////////
+// Shared by the constant folding of the parser and the interpreter.
+
+/// Applies an arithmetic or bitwise operator to two ints. Division truncates
+/// towards zero, overflow and division by zero are errors.
+pub fn int_arithmetic(operator: &ControlVariants, left: i64, right: i64) -> Result<i64, String> {
+    let result = match operator {
+        ControlVariants::Plus => left.checked_add(right),
+        ControlVariants::Minus => left.checked_sub(right),
+        ControlVariants::Multiplikation => left.checked_mul(right),
+        ControlVariants::Divide if right == 0 => return Err(String::from("division by zero")),
+        ControlVariants::Divide => left.checked_div(right),
+        ControlVariants::Power if right < 0 => {
+            return Err(format!("negative exponent {} for an int", right))
+        }
+        ControlVariants::Power => u32::try_from(right)
+            .ok()
+            .and_then(|right| left.checked_pow(right)),
+        ControlVariants::BitAnd => Some(left & right),
+        ControlVariants::BitOr => Some(left | right),
+        op => {
+            return Err(format!(
+                "operator '{}' cannot be applied to ints",
+                op.to_string()
+            ))
+        }
+    };
+    result.ok_or_else(|| {
+        format!(
+            "'{} {} {}' overflows an int",
+            left,
+            operator.to_string(),
+            right
+        )
+    })
+}
+
+/// Applies an arithmetic operator to two floats following IEEE 754.
+pub fn float_arithmetic(operator: &ControlVariants, left: f64, right: f64) -> Option<f64> {
+    match operator {
+        ControlVariants::Plus => Some(left + right),
+        ControlVariants::Minus => Some(left - right),
+        ControlVariants::Multiplikation => Some(left * right),
+        ControlVariants::Divide => Some(left / right),
+        ControlVariants::Power => Some(left.powf(right)),
+        _ => None,
+    }
+}
+
+/// Applies a comparison operator to the ordering of its operands, which is
+/// `None` for comparisons with NaN.
+pub fn comparison(operator: &ControlVariants, ordering: Option<Ordering>) -> Option<bool> {
+    let accepted: &[Ordering] = match operator {
+        ControlVariants::Less => &[Ordering::Less],
+        ControlVariants::LessEqual => &[Ordering::Less, Ordering::Equal],
+        ControlVariants::Greater => &[Ordering::Greater],
+        ControlVariants::GreaterEqual => &[Ordering::Greater, Ordering::Equal],
+        _ => return None,
+    };
+    Some(ordering.is_some_and(|ordering| accepted.contains(&ordering)))
+}
+
+/// Converts a float to an int by truncating towards zero.
+pub fn float_to_int(float: f64) -> Result<i64, String> {
+    let int = float.trunc();
+    // `i64::MAX as f64` is 2^63 and already out of range.
+    if int.is_finite() && int >= i64::MIN as f64 && int < i64::MAX as f64 {
+        Ok(int as i64)
+    } else {
+        Err(format!("{:?} does not fit into an int", float))
+    }
+}
+
+////////////////////////////////////////////////////////////////
+//                                                           ///
+//                     Expressions                           ///
+//                                                           ///
+////////////////////////////////////////////////////////////////
+/// Operators carry the `Position` of the operator token, every other node
+/// the `Position` of its first token.
+#[derive(Debug, Clone)]
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub value_type: Type,
+    pub position: Position,
+}
+impl Expression {
+    pub fn new(kind: ExpressionKind, value_type: Type, position: Position) -> Expression {
+        Expression {
+            kind,
+            value_type,
+            position,
+        }
+    }
+}
+impl Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ExpressionKind::Literal(literal) => write!(f, "{}", literal),
+            ExpressionKind::Identifier(name) => write!(f, "{}", name),
+            ExpressionKind::Binary {
+                operator,
+                left,
+                right,
+            } => write!(f, "({} {} {})", left, operator.to_string(), right),
+            ExpressionKind::Unary { operator, operand } => {
+                write!(f, "({}{})", operator.to_string(), operand)
+            }
+            ExpressionKind::Call { name, arguments } => write!(
+                f,
+                "{}({})",
+                name,
+                arguments
+                    .iter()
+                    .map(|argument| argument.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            ExpressionKind::Record { name, fields } => write!(
+                f,
+                "{} {{ {} }}",
+                name,
+                fields
+                    .iter()
+                    .map(|(field, value)| format!("{}: {}", field, value))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            ExpressionKind::Field { record, field } => write!(f, "{}.{}", record, field),
+            ExpressionKind::Array(elements) => write!(
+                f,
+                "[{}]",
+                elements
+                    .iter()
+                    .map(|element| element.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            ExpressionKind::Index { array, index, .. } => write!(f, "{}[{}]", array, index),
+            ExpressionKind::Conversion(value) => write!(f, "{}({})", self.value_type, value),
+            ExpressionKind::Ternary {
+                condition,
+                then,
+                otherwise,
+            } => write!(f, "({} ? {} : {})", condition, then, otherwise),
+            ExpressionKind::Interpolation(parts) => write!(
+                f,
+                "\"{}\"",
+                parts
+                    .iter()
+                    .map(|part| match &part.kind {
+                        ExpressionKind::Literal(Literal::Str(text)) => text.clone(),
+                        _ => format!("{{{}}}", part),
+                    })
+                    .collect::<String>()
+            ),
+            ExpressionKind::Match { value, arms } => write!(
+                f,
+                "match {} {{ {} }}",
+                value,
+                arms.iter()
+                    .map(|(pattern, expression)| match pattern {
+                        Some(pattern) => format!("{} => {}", pattern, expression),
+                        None => format!("_ => {}", expression),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ExpressionKind {
+    Literal(Literal),
+    Identifier(String),
+    Binary {
+        operator: ControlVariants,
+        left: Box<Expression>,
+        right: Box<Expression>,
+    },
+    Unary {
+        operator: ControlVariants,
+        operand: Box<Expression>,
+    },
+    Call {
+        name: String,
+        arguments: Vec<Expression>,
+    },
+    /// Fields are in the order of the type definition.
+    Record {
+        name: String,
+        fields: Vec<(String, Expression)>,
+    },
+    Field {
+        record: Box<Expression>,
+        field: String,
+    },
+    Array(Vec<Expression>),
+    Index {
+        array: Box<Expression>,
+        index: Box<Expression>,
+        /// Covers the whole `index`, whose own position is only the one of
+        /// its operator.
+        index_span: Position,
+    },
+    /// `int(value)` or `float(value)`, the target is the `value_type` of the
+    /// expression.
+    Conversion(Box<Expression>),
+    Ternary {
+        condition: Box<Expression>,
+        then: Box<Expression>,
+        otherwise: Box<Expression>,
+    },
+    /// The parts are converted to str and joined.
+    Interpolation(Vec<Expression>),
+    /// A pattern of `None` is the wildcard `_`.
+    Match {
+        value: Box<Expression>,
+        arms: Vec<(Option<Literal>, Expression)>,
+    },
+}
+
+////////////////////////////////////////////////////////////////
+//                                                           ///
+//                      Statements                           ///
+//                                                           ///
+////////////////////////////////////////////////////////////////
+#[derive(Debug, Clone)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub position: Position,
+}
+impl Statement {
+    pub fn new(kind: StatementKind, position: Position) -> Statement {
+        Statement { kind, position }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum StatementKind {
+    Expression(Expression),
+    Declaration {
+        name: String,
+        var_type: Type,
+        value: Expression,
+        is_const: bool,
+    },
+    /// The target is an identifier or a chain of field and index accesses on
+    /// one.
+    Asignment {
+        target: Expression,
+        value: Expression,
+    },
+    Block(Block),
+    If {
+        branches: Vec<(Expression, Block)>,
+        otherwise: Option<Block>,
+    },
+    While {
+        condition: Expression,
+        body: Block,
+    },
+    For {
+        init: Option<Box<Statement>>,
+        condition: Option<Expression>,
+        update: Option<Box<Statement>>,
+        body: Block,
+    },
+    ForEach {
+        name: String,
+        var_type: Type,
+        array: Expression,
+        body: Block,
+    },
+    Break,
+    Continue,
+    Function {
+        name: String,
+        parameter: Vec<(String, Type)>,
+        return_type: Type,
+        body: Block,
+    },
+    /// A function without a body, so it can be called before its definition
+    /// further down, like in mutual recursion.
+    FunctionDeclaration {
+        name: String,
+    },
+    Return(Option<Expression>),
+    Record {
+        name: String,
+        fields: Vec<(String, Type)>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub statements: Vec<Statement>,
+    pub position: Position,
+}
+impl Block {
+    pub fn new(statements: Vec<Statement>, position: Position) -> Block {
+        Block {
+            statements,
+            position,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn int_arithmetic_is_checked() {
+        let int = |operator, left, right| int_arithmetic(&operator, left, right);
+        assert_eq!(int(ControlVariants::Divide, 7, 2), Ok(3));
+        assert_eq!(int(ControlVariants::Divide, -7, 2), Ok(-3));
+        assert_eq!(int(ControlVariants::Power, 2, 62), Ok(1 << 62));
+        assert_eq!(int(ControlVariants::BitAnd, 6, 3), Ok(2));
+        assert_eq!(int(ControlVariants::BitOr, 6, 3), Ok(7));
+        for (operator, left, right) in [
+            (ControlVariants::Plus, i64::MAX, 1),
+            (ControlVariants::Minus, i64::MIN, 1),
+            (ControlVariants::Multiplikation, i64::MAX, 2),
+            (ControlVariants::Divide, i64::MIN, -1),
+            (ControlVariants::Divide, 1, 0),
+            (ControlVariants::Power, 2, 63),
+            (ControlVariants::Power, 2, -1),
+            (ControlVariants::Less, 1, 2),
+        ] {
+            assert!(
+                int(operator.clone(), left, right).is_err(),
+                "{:?}",
+                operator
+            );
+        }
+    }
+
+    #[test]
+    fn float_arithmetic_follows_ieee() {
+        let float = |operator, left, right| float_arithmetic(&operator, left, right);
+        assert_eq!(float(ControlVariants::Divide, 7.0, 2.0), Some(3.5));
+        assert_eq!(
+            float(ControlVariants::Divide, 1.0, 0.0),
+            Some(f64::INFINITY)
+        );
+        assert_eq!(float(ControlVariants::Power, 4.0, 0.5), Some(2.0));
+        assert_eq!(float(ControlVariants::BitAnd, 1.0, 1.0), None);
+    }
+
+    #[test]
+    fn comparisons() {
+        let less = Some(Ordering::Less);
+        assert_eq!(comparison(&ControlVariants::Less, less), Some(true));
+        assert_eq!(
+            comparison(&ControlVariants::GreaterEqual, less),
+            Some(false)
+        );
+        assert_eq!(
+            comparison(&ControlVariants::LessEqual, Some(Ordering::Equal)),
+            Some(true)
+        );
+        assert_eq!(comparison(&ControlVariants::Less, None), Some(false));
+        assert_eq!(comparison(&ControlVariants::Plus, less), None);
+    }
+
+    #[test]
+    fn float_to_int_truncates_and_checks_the_range() {
+        assert_eq!(float_to_int(2.9), Ok(2));
+        assert_eq!(float_to_int(-2.9), Ok(-2));
+        assert_eq!(float_to_int(-9223372036854775808.0), Ok(i64::MIN));
+        assert!(float_to_int(9223372036854775808.0).is_err());
+        assert!(float_to_int(f64::NAN).is_err());
+        assert!(float_to_int(f64::INFINITY).is_err());
+    }
+
+    #[test]
+    fn empty_arrays_are_named_without_an_element_type() {
+        let array = |element| Type::Array(Box::new(element));
+        assert_eq!(array(Type::Int).to_string(), "int[]");
+        assert_eq!(array(Type::Void).to_string(), "empty array");
+        assert_eq!(array(array(Type::Void)).to_string(), "empty array[]");
+    }
+}
@@ -0,0 +1,1056 @@
+use std::{cell::RefCell, collections::HashMap, fmt::Display, rc::Rc};
+
+use ops::{
+    ast::{
+        comparison, float_arithmetic, float_to_int, int_arithmetic, Block, Expression,
+        ExpressionKind, Literal, Statement, StatementKind, Type,
+    },
+    ControlVariants, ErrorHandler, ErrorTypes, Position, Res, TokenVariant,
+};
+
+#[derive(Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+    Void,
+    Record {
+        name: String,
+        fields: Vec<(String, Value)>,
+    },
+    Array(Vec<Value>),
+}
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int(int) => write!(f, "{}", int),
+            Self::Float(float) => write!(f, "{:?}", float),
+            Self::Str(str) => write!(f, "{}", str),
+            Self::Bool(bool) => write!(f, "{}", bool),
+            Self::Void => write!(f, "void"),
+            Self::Record { name, fields } => write!(
+                f,
+                "{} {{ {} }}",
+                name,
+                fields
+                    .iter()
+                    .map(|(field, value)| format!("{}: {}", field, value))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Self::Array(elements) => write!(
+                f,
+                "[{}]",
+                elements
+                    .iter()
+                    .map(|element| element.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+impl From<&Literal> for Value {
+    fn from(literal: &Literal) -> Self {
+        match literal {
+            Literal::Int(int) => Value::Int(*int),
+            Literal::Float(float) => Value::Float(*float),
+            Literal::Str(str) => Value::Str(str.clone()),
+            Literal::Bool(bool) => Value::Bool(*bool),
+        }
+    }
+}
+
+/// How control leaves a statement.
+enum Flow {
+    Normal,
+    Break,
+    Continue,
+    Return(Value),
+}
+
+/// A function together with the environment it was defined in.
+struct Closure<'a> {
+    parameter: &'a [(String, Type)],
+    body: &'a Block,
+    environment: Rc<RefCell<Environment<'a>>>,
+}
+
+struct Environment<'a> {
+    parent: Option<Rc<RefCell<Environment<'a>>>>,
+    variables: HashMap<String, Value>,
+    functions: HashMap<String, Rc<Closure<'a>>>,
+}
+impl<'a> Environment<'a> {
+    fn new(parent: Option<Rc<RefCell<Environment<'a>>>>) -> Rc<RefCell<Environment<'a>>> {
+        Rc::new(RefCell::new(Environment {
+            parent,
+            variables: HashMap::new(),
+            functions: HashMap::new(),
+        }))
+    }
+
+    fn get_variable(&self, name: &str) -> Option<Value> {
+        match self.variables.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self
+                .parent
+                .as_ref()
+                .and_then(|parent| parent.borrow().get_variable(name)),
+        }
+    }
+
+    fn set_variable(&mut self, name: &str, value: Value) -> bool {
+        match self.variables.get_mut(name) {
+            Some(variable) => {
+                *variable = value;
+                true
+            }
+            None => self
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.borrow_mut().set_variable(name, value)),
+        }
+    }
+
+    fn set_function(&mut self, name: &str, function: Rc<Closure<'a>>) -> bool {
+        match self.functions.get_mut(name) {
+            Some(old) => {
+                *old = function;
+                true
+            }
+            None => self
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.borrow_mut().set_function(name, function)),
+        }
+    }
+
+    fn get_function(&self, name: &str) -> Option<Rc<Closure<'a>>> {
+        match self.functions.get(name) {
+            Some(function) => Some(function.clone()),
+            None => self
+                .parent
+                .as_ref()
+                .and_then(|parent| parent.borrow().get_function(name)),
+        }
+    }
+}
+
+/// How deeply function calls may nest before the program is stopped.
+const MAX_CALL_DEPTH: usize = 1000;
+
+/// The stack of the thread the interpreter runs on. Every nested call takes
+/// several stack frames, so this leaves room for `MAX_CALL_DEPTH` calls even
+/// in unoptimized builds.
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// Runs `f` on a thread with a stack of `STACK_SIZE`, the stack of the main
+/// thread is too small for deeply nested calls.
+pub fn with_stack<T: Send>(f: impl FnOnce() -> T + Send) -> std::io::Result<T> {
+    std::thread::scope(|scope| {
+        let thread = std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn_scoped(scope, f)?;
+        Ok(thread
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+    })
+}
+
+pub struct Interpreter<'a> {
+    environment: Rc<RefCell<Environment<'a>>>,
+    depth: usize,
+}
+
+impl<'a> Interpreter<'a> {
+    pub fn new() -> Self {
+        Self {
+            environment: Environment::new(None),
+            depth: 0,
+        }
+    }
+
+    pub fn run(&mut self, statements: &'a [Statement]) -> Res<()> {
+        self.execute_statements(statements)?;
+        Ok(())
+    }
+
+    /// Functions are defined before the statements run, so they can be called
+    /// above their definition once they are declared. They are bound to the
+    /// environment of their declaration when it runs, see `capture`.
+    fn execute_statements(&mut self, statements: &'a [Statement]) -> Res<Flow> {
+        for statement in statements {
+            if let StatementKind::Function {
+                name,
+                parameter,
+                body,
+                ..
+            } = &statement.kind
+            {
+                let closure = Closure {
+                    parameter,
+                    body,
+                    environment: self.environment.clone(),
+                };
+                self.environment
+                    .borrow_mut()
+                    .functions
+                    .insert(name.clone(), Rc::new(closure));
+            }
+        }
+        for statement in statements {
+            match self.execute(statement)? {
+                Flow::Normal => (),
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    /// Runs `f` inside `environment` and restores the current one afterwards.
+    fn in_environment<T>(
+        &mut self,
+        environment: Rc<RefCell<Environment<'a>>>,
+        f: impl FnOnce(&mut Self) -> Res<T>,
+    ) -> Res<T> {
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let result = f(self);
+        self.environment = previous;
+        result
+    }
+
+    fn execute(&mut self, statement: &'a Statement) -> Res<Flow> {
+        match &statement.kind {
+            StatementKind::Expression(expression) => {
+                self.evaluate(expression)?;
+            }
+            StatementKind::Declaration { name, value, .. } => {
+                let value = self.evaluate(value)?;
+                self.environment
+                    .borrow_mut()
+                    .variables
+                    .insert(name.clone(), value);
+            }
+            StatementKind::Asignment { target, value } => {
+                let value = self.evaluate(value)?;
+                self.asign_place(target, value)?;
+            }
+            StatementKind::Block(block) => return self.execute_block(block),
+            StatementKind::If {
+                branches,
+                otherwise,
+            } => {
+                for (condition, body) in branches {
+                    if self.evaluate(condition)? == Value::Bool(true) {
+                        return self.execute_block(body);
+                    }
+                }
+                if let Some(body) = otherwise {
+                    return self.execute_block(body);
+                }
+            }
+            StatementKind::While { condition, body } => {
+                while self.evaluate(condition)? == Value::Bool(true) {
+                    match self.execute_block(body)? {
+                        Flow::Break => break,
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        Flow::Normal | Flow::Continue => (),
+                    }
+                }
+            }
+            StatementKind::For {
+                init,
+                condition,
+                update,
+                body,
+            } => {
+                let environment = Environment::new(Some(self.environment.clone()));
+                return self.in_environment(environment, |this| {
+                    this.execute_for(init, condition, update, body)
+                });
+            }
+            StatementKind::ForEach {
+                name, array, body, ..
+            } => {
+                let elements = match self.evaluate(array)? {
+                    Value::Array(elements) => elements,
+                    value => {
+                        return ErrorHandler::err(&format!("Cannot iterate over '{}'.", value))
+                    }
+                };
+                for element in elements {
+                    let environment = Environment::new(Some(self.environment.clone()));
+                    environment
+                        .borrow_mut()
+                        .variables
+                        .insert(name.clone(), element);
+                    match self.in_environment(environment, |this| this.execute_block(body))? {
+                        Flow::Break => break,
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        Flow::Normal | Flow::Continue => (),
+                    }
+                }
+            }
+            StatementKind::FunctionDeclaration { name } | StatementKind::Function { name, .. } => {
+                self.capture(name)
+            }
+            StatementKind::Record { .. } => (),
+            StatementKind::Break => return Ok(Flow::Break),
+            StatementKind::Continue => return Ok(Flow::Continue),
+            StatementKind::Return(value) => {
+                let value = match value {
+                    Some(value) => self.evaluate(value)?,
+                    None => Value::Void,
+                };
+                return Ok(Flow::Return(value));
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    /// Binds the function `name` to the current environment and continues in
+    /// a fresh one, so declarations after this point, like one shadowing a
+    /// variable the body uses, stay invisible to the function.
+    fn capture(&mut self, name: &str) {
+        let function = self.environment.borrow().get_function(name);
+        if let Some(function) = function {
+            let closure = Closure {
+                parameter: function.parameter,
+                body: function.body,
+                environment: self.environment.clone(),
+            };
+            self.environment
+                .borrow_mut()
+                .set_function(name, Rc::new(closure));
+        }
+        self.environment = Environment::new(Some(self.environment.clone()));
+    }
+
+    /// Stores `value` in `target`. Records and arrays have value semantics, so
+    /// asigning a field or element rebuilds the enclosing value and stores that
+    /// in turn.
+    fn asign_place(&mut self, target: &'a Expression, value: Value) -> Res<()> {
+        match &target.kind {
+            ExpressionKind::Identifier(name) => {
+                if !self.environment.borrow_mut().set_variable(name, value) {
+                    return ErrorHandler::compiler_err(
+                        ErrorTypes::UnexpectedSymbol,
+                        format!("expected a variable name, but found no '{}'", name),
+                        target.position.clone(),
+                    );
+                }
+                Ok(())
+            }
+            ExpressionKind::Field { record, field } => {
+                let mut parent = self.evaluate(record)?;
+                match &mut parent {
+                    Value::Record { fields, .. } => {
+                        if let Some((_, old)) = fields.iter_mut().find(|(name, _)| name == field) {
+                            *old = value;
+                        }
+                    }
+                    value => {
+                        return ErrorHandler::err(&format!(
+                            "Field '{}' cannot be asigned on '{}'.",
+                            field, value
+                        ))
+                    }
+                }
+                self.asign_place(record, parent)
+            }
+            ExpressionKind::Index {
+                array,
+                index,
+                index_span,
+            } => {
+                let mut elements = match self.evaluate(array)? {
+                    Value::Array(elements) => elements,
+                    value => {
+                        return ErrorHandler::err(&format!(
+                            "Element cannot be asigned on '{}'.",
+                            value
+                        ))
+                    }
+                };
+                let i = self.index(elements.len(), index, index_span)?;
+                elements[i] = value;
+                self.asign_place(array, Value::Array(elements))
+            }
+            _ => ErrorHandler::compiler_err(
+                ErrorTypes::InvalidStatement,
+                format!(
+                    "expected a variable, field or element but found '{}'",
+                    target
+                ),
+                target.position.clone(),
+            ),
+        }
+    }
+
+    /// Evaluates `index` and checks that it is a valid index into an array of
+    /// `length` elements. Errors point at the `span` of the whole index.
+    fn index(&mut self, length: usize, index: &'a Expression, span: &Position) -> Res<usize> {
+        match self.evaluate(index)? {
+            Value::Int(int) => match usize::try_from(int) {
+                Ok(i) if i < length => Ok(i),
+                _ => ErrorHandler::compiler_err(
+                    ErrorTypes::IndexOutOfBounds,
+                    format!(
+                        "index {} is out of bounds for an array of length {}",
+                        int, length
+                    ),
+                    span.clone(),
+                ),
+            },
+            value => ErrorHandler::err(&format!("'{}' cannot be used as an index.", value)),
+        }
+    }
+
+    fn execute_for(
+        &mut self,
+        init: &'a Option<Box<Statement>>,
+        condition: &'a Option<Expression>,
+        update: &'a Option<Box<Statement>>,
+        body: &'a Block,
+    ) -> Res<Flow> {
+        if let Some(init) = init {
+            self.execute(init)?;
+        }
+        loop {
+            if let Some(condition) = condition {
+                if self.evaluate(condition)? != Value::Bool(true) {
+                    break;
+                }
+            }
+            match self.execute_block(body)? {
+                Flow::Break => break,
+                Flow::Return(value) => return Ok(Flow::Return(value)),
+                Flow::Normal | Flow::Continue => (),
+            }
+            if let Some(update) = update {
+                self.execute(update)?;
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn execute_block(&mut self, block: &'a Block) -> Res<Flow> {
+        let environment = Environment::new(Some(self.environment.clone()));
+        self.in_environment(environment, |this| {
+            this.execute_statements(&block.statements)
+        })
+    }
+
+    fn evaluate(&mut self, expression: &'a Expression) -> Res<Value> {
+        match &expression.kind {
+            ExpressionKind::Literal(literal) => Ok(Value::from(literal)),
+            ExpressionKind::Identifier(name) => {
+                match self.environment.borrow().get_variable(name) {
+                    Some(value) => Ok(value),
+                    None => ErrorHandler::compiler_err(
+                        ErrorTypes::UnexpectedSymbol,
+                        format!("expected a variable name, but found no '{}'", name),
+                        expression.position.clone(),
+                    ),
+                }
+            }
+            ExpressionKind::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                let left = self.evaluate(left)?;
+                match (operator, &left) {
+                    (ControlVariants::And, Value::Bool(false)) => return Ok(left),
+                    (ControlVariants::Or, Value::Bool(true)) => return Ok(left),
+                    _ => (),
+                }
+                let right = self.evaluate(right)?;
+                Self::binary(operator, left, right, &expression.position)
+            }
+            ExpressionKind::Unary { operator, operand } => {
+                match (operator, self.evaluate(operand)?) {
+                    (ControlVariants::Minus, Value::Int(int)) => match int.checked_neg() {
+                        Some(int) => Ok(Value::Int(int)),
+                        None => ErrorHandler::compiler_err(
+                            ErrorTypes::ArithmeticError,
+                            format!("'-{}' overflows an int", int),
+                            expression.position.clone(),
+                        ),
+                    },
+                    (ControlVariants::Minus, Value::Float(float)) => Ok(Value::Float(-float)),
+                    (ControlVariants::Not, Value::Bool(bool)) => Ok(Value::Bool(!bool)),
+                    (op, value) => ErrorHandler::err(&format!(
+                        "Operator '{}' cannot be applied to '{}'.",
+                        op.to_string(),
+                        value
+                    )),
+                }
+            }
+            ExpressionKind::Conversion(value) => {
+                match (&expression.value_type, self.evaluate(value)?) {
+                    (Type::Int, Value::Float(float)) => match float_to_int(float) {
+                        Ok(int) => Ok(Value::Int(int)),
+                        Err(message) => ErrorHandler::compiler_err(
+                            ErrorTypes::ArithmeticError,
+                            message,
+                            expression.position.clone(),
+                        ),
+                    },
+                    (Type::Float, Value::Int(int)) => Ok(Value::Float(int as f64)),
+                    (_, value) => Ok(value),
+                }
+            }
+            ExpressionKind::Ternary {
+                condition,
+                then,
+                otherwise,
+            } => {
+                if self.evaluate(condition)? == Value::Bool(true) {
+                    self.evaluate(then)
+                } else {
+                    self.evaluate(otherwise)
+                }
+            }
+            ExpressionKind::Interpolation(parts) => parts
+                .iter()
+                .map(|part| self.evaluate(part).map(|value| value.to_string()))
+                .collect::<Res<String>>()
+                .map(Value::Str),
+            ExpressionKind::Match { value, arms } => {
+                let value = self.evaluate(value)?;
+                for (pattern, arm) in arms {
+                    if pattern
+                        .as_ref()
+                        .is_none_or(|pattern| Value::from(pattern) == value)
+                    {
+                        return self.evaluate(arm);
+                    }
+                }
+                ErrorHandler::compiler_err(
+                    ErrorTypes::NonExhaustiveMatch,
+                    format!("no arm matches '{}'", value),
+                    expression.position.clone(),
+                )
+            }
+            ExpressionKind::Record { name, fields } => {
+                let fields = fields
+                    .iter()
+                    .map(|(field, value)| Ok((field.clone(), self.evaluate(value)?)))
+                    .collect::<Res<Vec<(String, Value)>>>()?;
+                Ok(Value::Record {
+                    name: name.clone(),
+                    fields,
+                })
+            }
+            ExpressionKind::Field { record, field } => match self.evaluate(record)? {
+                Value::Record { fields, .. } => fields
+                    .into_iter()
+                    .find(|(name, _)| name == field)
+                    .map(|(_, value)| value)
+                    .map_or_else(
+                        || ErrorHandler::err(&format!("Field '{}' does not exist.", field)),
+                        Ok,
+                    ),
+                value => ErrorHandler::err(&format!(
+                    "Field '{}' cannot be read from '{}'.",
+                    field, value
+                )),
+            },
+            ExpressionKind::Array(elements) => elements
+                .iter()
+                .map(|element| self.evaluate(element))
+                .collect::<Res<Vec<Value>>>()
+                .map(Value::Array),
+            ExpressionKind::Index {
+                array,
+                index,
+                index_span,
+            } => match self.evaluate(array)? {
+                Value::Array(mut elements) => {
+                    let i = self.index(elements.len(), index, index_span)?;
+                    Ok(elements.swap_remove(i))
+                }
+                value => ErrorHandler::err(&format!("'{}' cannot be indexed.", value)),
+            },
+            ExpressionKind::Call { name, arguments } => {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| self.evaluate(argument))
+                    .collect::<Res<Vec<Value>>>()?;
+                self.call(name, arguments, expression)
+            }
+        }
+    }
+
+    /// Applies `operator`, errors of int arithmetic are reported at `position`.
+    fn binary(
+        operator: &ControlVariants,
+        left: Value,
+        right: Value,
+        position: &Position,
+    ) -> Res<Value> {
+        Ok(match (operator, left, right) {
+            (ControlVariants::Equal, l, r) => Value::Bool(l == r),
+            (ControlVariants::NotEqual, l, r) => Value::Bool(l != r),
+            (ControlVariants::Plus, Value::Str(l), Value::Str(r)) => Value::Str(l + &r),
+            (op, Value::Int(l), Value::Int(r)) => match comparison(op, l.partial_cmp(&r)) {
+                Some(bool) => Value::Bool(bool),
+                None => match int_arithmetic(op, l, r) {
+                    Ok(int) => Value::Int(int),
+                    Err(message) => {
+                        return ErrorHandler::compiler_err(
+                            ErrorTypes::ArithmeticError,
+                            message,
+                            position.clone(),
+                        )
+                    }
+                },
+            },
+            (op, Value::Float(l), Value::Float(r)) => match comparison(op, l.partial_cmp(&r)) {
+                Some(bool) => Value::Bool(bool),
+                None => match float_arithmetic(op, l, r) {
+                    Some(float) => Value::Float(float),
+                    None => {
+                        return ErrorHandler::err(&format!(
+                            "Operator '{}' cannot be applied to '{:?}' and '{:?}'.",
+                            op.to_string(),
+                            l,
+                            r
+                        ))
+                    }
+                },
+            },
+            (ControlVariants::And, Value::Bool(l), Value::Bool(r)) => Value::Bool(l && r),
+            (ControlVariants::Or, Value::Bool(l), Value::Bool(r)) => Value::Bool(l || r),
+            (op, l, r) => {
+                return ErrorHandler::err(&format!(
+                    "Operator '{}' cannot be applied to '{}' and '{}'.",
+                    op.to_string(),
+                    l,
+                    r
+                ))
+            }
+        })
+    }
+
+    fn call(&mut self, name: &str, arguments: Vec<Value>, call: &Expression) -> Res<Value> {
+        let function = self.environment.borrow().get_function(name);
+        if let Some(function) = function {
+            if self.depth == MAX_CALL_DEPTH {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::StackOverflow,
+                    format!(
+                        "calling '{}' exceeds the limit of {} nested calls",
+                        name, MAX_CALL_DEPTH
+                    ),
+                    call.position.clone(),
+                );
+            }
+            let environment = Environment::new(Some(function.environment.clone()));
+            for ((parameter, _), argument) in function.parameter.iter().zip(arguments) {
+                environment
+                    .borrow_mut()
+                    .variables
+                    .insert(parameter.clone(), argument);
+            }
+            self.depth += 1;
+            let flow = self.in_environment(environment, |this| {
+                this.execute_statements(&function.body.statements)
+            });
+            self.depth -= 1;
+            return match flow? {
+                Flow::Return(value) => Ok(value),
+                _ => Ok(Value::Void),
+            };
+        }
+        match name {
+            "print" => {
+                println!(
+                    "{}",
+                    arguments
+                        .iter()
+                        .map(|argument| argument.to_string())
+                        .collect::<Vec<String>>()
+                        .join(" ")
+                );
+                Ok(Value::Void)
+            }
+            "len" => match &arguments[..] {
+                [Value::Array(elements)] => Ok(Value::Int(elements.len() as i64)),
+                [Value::Str(str)] => Ok(Value::Int(str.chars().count() as i64)),
+                _ => ErrorHandler::err("'len' expects a single array or str."),
+            },
+            _ => ErrorHandler::compiler_err(
+                ErrorTypes::UnexpectedSymbol,
+                format!("expected a function name, but found no '{}'", name),
+                call.position.clone(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::Scope, tokenizer::tokenizer};
+    use ops::{CompilerError, Diagnostics};
+    use std::io::BufReader;
+
+    fn parse(source: &str) -> Vec<Statement> {
+        let diagnostics = Diagnostics::new();
+        let tokens = tokenizer(BufReader::new(source.as_bytes()), "test", &diagnostics).unwrap();
+        let program = Scope::new(&diagnostics).parse_block(tokens).unwrap();
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics.into_errors());
+        program
+    }
+
+    /// Runs `source` and returns the printed value of the variable `name`.
+    fn value(source: &str, name: &str) -> String {
+        let program = parse(source);
+        let mut interpreter = Interpreter::new();
+        interpreter.run(&program).unwrap();
+        let value = interpreter.environment.borrow().get_variable(name);
+        value.unwrap().to_string()
+    }
+
+    /// Runs `source` and returns the runtime error it fails with.
+    fn run_error(source: &str) -> CompilerError {
+        let program = parse(source);
+        let error = Interpreter::new().run(&program).unwrap_err();
+        *error.downcast::<CompilerError>().unwrap()
+    }
+
+    #[test]
+    fn operators_follow_precedence_and_associativity() {
+        assert_eq!(value("int x = 10 - 3 - 2;", "x"), "5");
+        assert_eq!(value("int x = 1 + 2 * 3;", "x"), "7");
+        assert_eq!(value("int x = 100 / 10 / 5;", "x"), "2");
+        assert_eq!(value("bool x = 1 + 1 < 3;", "x"), "true");
+    }
+
+    #[test]
+    fn parentheses_and_unary_operators() {
+        assert_eq!(value("int x = (1 + 2) * 3;", "x"), "9");
+        assert_eq!(value("int x = -(2 + 3);", "x"), "-5");
+        assert_eq!(value("bool x = !(1 < 2) || !false;", "x"), "true");
+    }
+
+    #[test]
+    fn variable_references() {
+        assert_eq!(value("int y = 4;\nint x = y * y;", "x"), "16");
+        assert_eq!(value("int x = 1;\nx = x + 1;\nx = x * 3;", "x"), "6");
+    }
+
+    #[test]
+    fn scopes_and_shadowing() {
+        assert_eq!(value("int x = 1;\n{ int x = 2; x = 3; };", "x"), "1");
+        assert_eq!(value("int x = 1;\n{ { x = x + 2; }; };", "x"), "3");
+        assert_eq!(
+            value("int x = 1;\n{ str x = \"a\"; };\nint y = x + 1;", "y"),
+            "2"
+        );
+    }
+
+    #[test]
+    fn if_chains_take_the_first_true_branch() {
+        let source = |n: i64| {
+            format!(
+                "int n = {};\nstr x = \"\";
+                if (n < 0) {{ x = \"negative\"; }}
+                else if (n < 10) {{ x = \"small\"; }}
+                else if (n < 20) {{ x = \"medium\"; }}
+                else {{ x = \"large\"; }};",
+                n
+            )
+        };
+        assert_eq!(value(&source(-1), "x"), "negative");
+        assert_eq!(value(&source(5), "x"), "small");
+        assert_eq!(value(&source(15), "x"), "medium");
+        assert_eq!(value(&source(25), "x"), "large");
+        assert_eq!(value("int x = 1;\nif (false) { x = 2; };", "x"), "1");
+    }
+
+    #[test]
+    fn while_loops_with_break_and_continue() {
+        let source = "int i = 0;\nint sum = 0;
+            while (true) {
+                i = i + 1;
+                if (i > 6) { break; };
+                if (i > 2 && i < 4) { continue; };
+                sum = sum + i;
+            };";
+        assert_eq!(value(source, "sum"), "18");
+        assert_eq!(value(source, "i"), "7");
+        assert_eq!(value("int i = 0;\nwhile (i < 0) { i = 1; };", "i"), "0");
+    }
+
+    #[test]
+    fn for_loops_with_break_and_continue() {
+        let source = "int sum = 0;
+            for (int i = 0; i < 10; i = i + 1) {
+                if (i > 1 && i < 3) { continue; };
+                if (i > 4) { break; };
+                sum = sum + i;
+            };";
+        assert_eq!(value(source, "sum"), "8");
+        assert_eq!(
+            value("int n = 0;\nfor (;;) { n = n + 1; break; };", "n"),
+            "1"
+        );
+    }
+
+    #[test]
+    fn functions() {
+        let source = "func fac args(int n): int {
+                if (n < 2) { return 1; };
+                return n * fac(n - 1);
+            };
+            int x = fac(10);";
+        assert_eq!(value(source, "x"), "3628800");
+        let source = "int calls = 0;
+            func first args(int from, int n): int {
+                calls = calls + 1;
+                for (int v = from; v < 10; v = v + 4) { if (v > n) { return v; }; };
+                return -1;
+            };
+            int x = first(1, 4);
+            int y = first(1, 9);";
+        assert_eq!(value(source, "x"), "5");
+        assert_eq!(value(source, "y"), "-1");
+        assert_eq!(value(source, "calls"), "2");
+    }
+
+    #[test]
+    fn functions_can_be_called_above_their_definition() {
+        let source = "func odd args(int n): bool;
+            func even args(int n): bool;
+            bool x = odd(7);
+            func odd args(int n): bool { if (n == 0) { return false; }; return even(n - 1); };
+            func even args(int n): bool { if (n == 0) { return true; }; return odd(n - 1); };";
+        assert_eq!(value(source, "x"), "true");
+    }
+
+    #[test]
+    fn match_takes_the_first_matching_arm() {
+        let source = |x: &str| {
+            format!(
+                "str x = \"{}\";\nint y = match x {{ \"a\" => 1, \"b\" => 2, _ => 3 }};",
+                x
+            )
+        };
+        assert_eq!(value(&source("a"), "y"), "1");
+        assert_eq!(value(&source("b"), "y"), "2");
+        assert_eq!(value(&source("c"), "y"), "3");
+        assert_eq!(
+            value(
+                "bool b = false;\nint y = match b { true => 1, false => 2 };",
+                "y"
+            ),
+            "2"
+        );
+    }
+
+    #[test]
+    fn records_have_value_semantics() {
+        let source = "def P { int x, int y };
+            def Line { P from, P to };
+            P p = P { x: 1, y: 2 };
+            P q = p;
+            q.x = 5;
+            Line l = Line { from: p, to: q };
+            l.to.y = 7;
+            func move args(P p): int { p.x = 9; return p.x; };
+            int moved = move(p);";
+        assert_eq!(value(source, "p"), "P { x: 1, y: 2 }");
+        assert_eq!(value(source, "q"), "P { x: 5, y: 2 }");
+        assert_eq!(
+            value(source, "l"),
+            "Line { from: P { x: 1, y: 2 }, to: P { x: 5, y: 7 } }"
+        );
+        assert_eq!(value(source, "moved"), "9");
+    }
+
+    #[test]
+    fn arrays_have_value_semantics() {
+        let source = "int[][] m = [[1, 2], [3]];
+            int[][] n = m;
+            n[1][0] = 4;
+            int[] row = m[0];
+            row[0] = 5;";
+        assert_eq!(value(source, "m"), "[[1, 2], [3]]");
+        assert_eq!(value(source, "n"), "[[1, 2], [4]]");
+        assert_eq!(value(source, "row"), "[5, 2]");
+        assert_eq!(value("int n = len([1, 2, 3]) + len(\"äö\");", "n"), "5");
+        assert_eq!(value("int[] a = [];\nint n = len(a);", "n"), "0");
+    }
+
+    #[test]
+    fn indices_are_bounds_checked() {
+        for index in ["2", "-1", "9223372036854775807"] {
+            assert_eq!(
+                run_error(&format!("int[] a = [1, 2];\nint x = a[{}];", index)).error_type(),
+                ErrorTypes::IndexOutOfBounds
+            );
+        }
+    }
+
+    #[test]
+    fn for_each_loops_iterate_over_copies() {
+        let source = "int sum = 0;\nint[] a = [1, 2, 3, 4];
+            for (int v : a) {
+                if (v > 1 && v < 3) { continue; };
+                if (v > 3) { break; };
+                v = 0 - v;
+                sum = sum + v;
+            };
+            int first = a[0];";
+        assert_eq!(value(source, "sum"), "-4");
+        assert_eq!(value(source, "first"), "1");
+    }
+
+    #[test]
+    fn ternaries_evaluate_one_branch() {
+        let source = |n: i64| {
+            format!(
+                "int[] a = [1];\nint n = {};\nint x = n < 0 ? -1 : n < len(a) ? a[n] : 0;",
+                n
+            )
+        };
+        assert_eq!(value(&source(-5), "x"), "-1");
+        assert_eq!(value(&source(0), "x"), "1");
+        assert_eq!(value(&source(5), "x"), "0");
+    }
+
+    #[test]
+    fn multi_character_operators() {
+        assert_eq!(value("int x = 2 ** 3 ** 2;", "x"), "512");
+        assert_eq!(value("int x = -2 ** 2;", "x"), "-4");
+        assert_eq!(value("bool x = 2 >= 2 && 1 != 2;", "x"), "true");
+    }
+
+    #[test]
+    fn int_and_float_arithmetic() {
+        assert_eq!(value("int x = 7 / 2;", "x"), "3");
+        assert_eq!(value("float x = 7.0 / 2.0;", "x"), "3.5");
+        assert_eq!(value("float x = 1.0 / 0.0;", "x"), "inf");
+        assert_eq!(value("int x = int(-2.9);", "x"), "-2");
+        assert_eq!(value("float x = float(3);", "x"), "3.0");
+    }
+
+    #[test]
+    fn int_overflow_is_an_error() {
+        for expression in [
+            "x + 1",
+            "-x - 2",
+            "x * 2",
+            "1 / (x - x)",
+            "2 ** 63",
+            "2 ** -1",
+            "int(1e19)",
+            "-(-x - 1)",
+        ] {
+            let source = format!("int x = 9223372036854775807;\nint y = {};", expression);
+            assert_eq!(
+                run_error(&source).error_type(),
+                ErrorTypes::ArithmeticError,
+                "{}",
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn smallest_int_literal() {
+        assert_eq!(
+            value("int x = -9223372036854775808;", "x"),
+            i64::MIN.to_string()
+        );
+        assert_eq!(value("int x = -2 ** 2;", "x"), "-4");
+        assert_eq!(value("int x = 3 -2;", "x"), "1");
+        assert_eq!(value("int y = 3;\nint x = -(y + 2);", "x"), "-5");
+        assert_eq!(value("float x = -2.5;", "x"), "-2.5");
+    }
+
+    #[test]
+    fn index_errors_point_at_the_index() {
+        let error = run_error("int[] a = [1, 2];\nint x = a[1 + 5];");
+        assert_eq!(error.error_type(), ErrorTypes::IndexOutOfBounds);
+        let span = |start, end| Position::span(String::from("test"), start, end);
+        assert_eq!(error.position(), &span((2, 11), (2, 16)));
+        let error = run_error("int[] a = [1, 2];\na[-1] = 3;");
+        assert_eq!(error.position(), &span((2, 3), (2, 5)));
+        assert_eq!(
+            value("int[] a = [1, 2];\na[1] = 3;\nint x = a[1];", "x"),
+            "3"
+        );
+    }
+
+    #[test]
+    fn deep_recursion_is_an_error() {
+        let count = "func count args(int n): int {
+                if (n == 0) { return 0; };
+                return 1 + count(n - 1);
+            };\n";
+        let source = format!("{}int x = count({});", count, MAX_CALL_DEPTH - 1);
+        assert_eq!(with_stack(|| value(&source, "x")).unwrap(), "999");
+        let source = format!("{}int x = count({});", count, MAX_CALL_DEPTH);
+        let error = with_stack(|| run_error(&source)).unwrap();
+        assert_eq!(error.error_type(), ErrorTypes::StackOverflow);
+        let span = |start, end| Position::span(String::from("test"), start, end);
+        assert_eq!(error.position(), &span((3, 28), (3, 33)));
+    }
+
+    #[test]
+    fn functions_capture_their_definition_environment() {
+        let source = "int x = 1;\nint y = 0;
+            {
+                func f: int { return x; };
+                str x = \"a\";
+                y = f();
+            };";
+        assert_eq!(value(source, "y"), "1");
+        let source = "int x = 1;
+            func f: int { return x; };
+            x = 5;
+            int y = f();";
+        assert_eq!(value(source, "y"), "5");
+    }
+
+    #[test]
+    fn explanation_examples_fail_with_their_code() {
+        for error_type in ErrorTypes::ALL {
+            let (_, example) = error_type
+                .explanation()
+                .split_once("Erroneous code example:\n\n")
+                .unwrap();
+            let source: String = example
+                .lines()
+                .take_while(|line| line.is_empty() || line.starts_with("    "))
+                .map(|line| format!("{}\n", line.get(4..).unwrap_or("")))
+                .collect();
+            let diagnostics = Diagnostics::new();
+            let tokens =
+                tokenizer(BufReader::new(source.as_bytes()), "test", &diagnostics).unwrap();
+            let program = Scope::new(&diagnostics).parse_block(tokens).unwrap();
+            let mut codes: Vec<&str> = diagnostics
+                .into_errors()
+                .iter()
+                .map(|error| error.error_type().code())
+                .collect();
+            if codes.is_empty() {
+                let result = with_stack(|| {
+                    Interpreter::new()
+                        .run(&program)
+                        .map_err(|error| error.downcast::<CompilerError>().unwrap().error_type())
+                });
+                codes.extend(result.unwrap().err().map(|error| error.code()));
+            }
+            codes.dedup();
+            assert_eq!(codes, [error_type.code()], "{}", source);
+        }
+    }
+}
@@ -1,23 +1,73 @@
 use std::{
     any::Any,
+    cell::RefCell,
     error::Error,
     fmt::{Debug, Display},
 };
 
+pub mod ast;
+
 ////////////////////////////////////////////////////////////////
 //                                                           ///
 //                      Position                             ///
 //                                                           ///
 ////////////////////////////////////////////////////////////////
-#[derive(Debug, Clone)]
+/// A span of source code from `row` and `column` up to but excluding
+/// `end_row` and `end_column`, all starting at 1. Columns count characters,
+/// not bytes. Positions order by file and start, so diagnostics can be
+/// reported in the order they appear in the source.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Position {
+    file: String,
     row: usize,
     column: usize,
-    file: String,
+    end_row: usize,
+    end_column: usize,
 }
 impl Position {
+    /// An empty span at `row` and `column`.
     pub fn new(file: String, row: usize, column: usize) -> Position {
-        Position { file, row, column }
+        Position::span(file, (row, column), (row, column))
+    }
+
+    /// The span from `start` up to `end`, both given as row and column.
+    pub fn span(file: String, start: (usize, usize), end: (usize, usize)) -> Position {
+        Position {
+            file,
+            row: start.0,
+            column: start.1,
+            end_row: end.0,
+            end_column: end.1,
+        }
+    }
+
+    /// The span from the start of `self` up to the end of `end`.
+    pub fn to(&self, end: &Position) -> Position {
+        Position::span(
+            self.file.clone(),
+            (self.row, self.column),
+            (end.end_row, end.end_column),
+        )
+    }
+
+    /// Maps a position in a snippet of source code that starts at `origin`
+    /// back to the position in the whole file.
+    pub fn relative_to(&self, origin: &Position) -> Position {
+        let shift = |row: usize, column: usize| {
+            (
+                origin.row + row - 1,
+                if row == 1 {
+                    origin.column + column - 1
+                } else {
+                    column
+                },
+            )
+        };
+        Position::span(
+            origin.file.clone(),
+            shift(self.row, self.column),
+            shift(self.end_row, self.end_column),
+        )
     }
 }
 impl Display for Position {
@@ -33,33 +83,313 @@ impl Display for Position {
 ////////////////////////////////////////////////////////////////
 pub type Res<T> = Result<T, Box<dyn Error>>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ErrorTypes {
     UnclosedBlock,
     UnclosedString,
+    UnclosedComment,
     UnclosedStatement,
     InvalidStatement,
     UnexpectedSymbol,
     MismatchedTypes,
+    DuplicateDefinition,
+    ImmutableAsignment,
+    NonExhaustiveMatch,
+    IndexOutOfBounds,
+    ArithmeticError,
+    UnreachablePattern,
+    StackOverflow,
+}
+impl ErrorTypes {
+    pub const ALL: [ErrorTypes; 14] = [
+        Self::UnclosedBlock,
+        Self::UnclosedString,
+        Self::UnclosedComment,
+        Self::UnclosedStatement,
+        Self::InvalidStatement,
+        Self::UnexpectedSymbol,
+        Self::MismatchedTypes,
+        Self::DuplicateDefinition,
+        Self::ImmutableAsignment,
+        Self::NonExhaustiveMatch,
+        Self::IndexOutOfBounds,
+        Self::ArithmeticError,
+        Self::UnreachablePattern,
+        Self::StackOverflow,
+    ];
+
+    /// The stable identifier of the error. Codes are never reused or
+    /// renumbered, new error types get the next free one.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UnclosedString => "E0001",
+            Self::UnclosedBlock => "E0002",
+            Self::UnclosedStatement => "E0003",
+            Self::InvalidStatement => "E0004",
+            Self::UnexpectedSymbol => "E0005",
+            Self::MismatchedTypes => "E0006",
+            Self::DuplicateDefinition => "E0007",
+            Self::ImmutableAsignment => "E0008",
+            Self::NonExhaustiveMatch => "E0009",
+            Self::UnclosedComment => "E0010",
+            Self::IndexOutOfBounds => "E0011",
+            Self::ArithmeticError => "E0012",
+            Self::UnreachablePattern => "E0013",
+            Self::StackOverflow => "E0014",
+        }
+    }
+
+    /// Looks up an error type by its code, ignoring the case of the `E`.
+    pub fn from_code(code: &str) -> Option<ErrorTypes> {
+        Self::ALL
+            .into_iter()
+            .find(|error_type| error_type.code().eq_ignore_ascii_case(code))
+    }
+
+    /// A long-form description of the error with an example and a fix.
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            Self::UnclosedString => {
+                r#"A string was opened with '"' but the file ended before it was closed.
+
+Erroneous code example:
+
+    str greeting = "Hello;
+
+Strings may span several lines, so the missing '"' is only noticed at the
+end of the file. Close the string where it is meant to end:
+
+    str greeting = "Hello";"#
+            }
+            Self::UnclosedBlock => {
+                r#"A block or bracket was opened but not closed before the statement ended.
+
+Erroneous code example:
+
+    if (true) {
+        print("yes");
+    ;
+
+Close every '{', '(' and '[' with its counterpart:
+
+    if (true) {
+        print("yes");
+    };"#
+            }
+            Self::UnclosedStatement => {
+                r#"A statement was not terminated with ';'.
+
+Erroneous code example:
+
+    while (false) {
+        print("never")
+    };
+
+Every statement, including the last one of a block and blocks, functions
+and loops themselves, ends with ';':
+
+    while (false) {
+        print("never");
+    };"#
+            }
+            Self::InvalidStatement => {
+                r#"A statement or expression does not follow the grammar of the language,
+for example because a part of it is missing.
+
+Erroneous code example:
+
+    int x = ;
+
+Complete the statement with the missing part:
+
+    int x = 1;"#
+            }
+            Self::UnexpectedSymbol => {
+                r#"A symbol was found where it cannot be used, for example an unknown name,
+a malformed number or a '}' without an opened block.
+
+Erroneous code example:
+
+    int x = 1__0;
+    print(y);
+
+Use only defined names and well-formed literals:
+
+    int x = 1_0;
+    print(x);"#
+            }
+            Self::MismatchedTypes => {
+                r#"A value of one type was used where another type is expected. Values are
+never converted implicitly, not even between int and float.
+
+Erroneous code example:
+
+    int x = 1.5;
+    float y = x + 0.5;
+
+Use a value of the expected type or convert it explicitly:
+
+    int x = int(1.5);
+    float y = float(x) + 0.5;"#
+            }
+            Self::DuplicateDefinition => {
+                r#"A name was defined twice in the same scope. This applies to variables,
+functions, records, parameters and the fields of a record.
+
+Erroneous code example:
+
+    int x = 1;
+    int x = 2;
+
+Choose another name, or asign to the existing variable:
+
+    int x = 1;
+    x = 2;"#
+            }
+            Self::ImmutableAsignment => {
+                r#"A value was asigned to a constant. Constants keep the value they are
+declared with.
+
+Erroneous code example:
+
+    const int LIMIT = 10;
+    LIMIT = 20;
+
+Declare a variable without 'const' if it needs to change:
+
+    int limit = 10;
+    limit = 20;"#
+            }
+            Self::NonExhaustiveMatch => {
+                r#"A 'match' does not cover every possible value.
+
+Erroneous code example:
+
+    int x = 3;
+    str name = match x { 1 => "one", 2 => "two" };
+
+Add the missing arms, or a '_' arm for all remaining values:
+
+    int x = 3;
+    str name = match x { 1 => "one", 2 => "two", _ => "many" };"#
+            }
+            Self::UnclosedComment => {
+                r#"A block comment was opened with '/*' but the file ended before it was
+closed. Block comments nest, so every '/*' inside needs its own '*/'.
+
+Erroneous code example:
+
+    /* outer /* inner */
+    print("hidden");
+
+Close every opened block comment:
+
+    /* outer /* inner */ */
+    print("shown");"#
+            }
+            Self::IndexOutOfBounds => {
+                r#"An array was indexed at a position it does not have while running the
+program. Indices start at 0 and must be less than the length of the array.
+
+Erroneous code example:
+
+    int[] values = [1, 2, 3];
+    print(values[3]);
+
+Check the index against the length of the array:
+
+    int i = 3;
+    if (i < len(values)) {
+        print(values[i]);
+    };"#
+            }
+            Self::ArithmeticError => {
+                r#"An arithmetic operation has no int result while running the program:
+a division by zero, a negative exponent, a result too large for an int or
+a float converted to int that does not fit.
+
+Erroneous code example:
+
+    int zero = 0;
+    print(1 / zero);
+
+Check the operands before the operation, or compute with floats:
+
+    if (zero != 0) {
+        print(1 / zero);
+    };"#
+            }
+            Self::UnreachablePattern => {
+                r#"A 'match' arm can never be chosen, because the arms before it already
+cover its pattern. This is a warning, the program still compiles.
+
+Erroneous code example:
+
+    int x = 3;
+    str name = match x { 1 => "one", _ => "many", 2 => "two" };
+
+Remove the arm, or move it before the arm that covers it:
+
+    int x = 3;
+    str name = match x { 1 => "one", 2 => "two", _ => "many" };"#
+            }
+            Self::StackOverflow => {
+                r#"Function calls nested too deeply while running the program, usually
+because a recursive function never reaches the case that stops it.
+
+Erroneous code example:
+
+    func count args(int n): int {
+        return 1 + count(n - 1);
+    };
+    print(count(3));
+
+Stop the recursion with a base case, or use a loop for deep iterations:
+
+    func count args(int n): int {
+        if (n == 0) { return 0; };
+        return 1 + count(n - 1);
+    };
+    print(count(3));"#
+            }
+        }
+    }
 }
 impl Display for ErrorTypes {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let message = match self {
             Self::UnclosedString => "Unclosed String",
+            Self::UnclosedComment => "Unclosed Comment",
             Self::UnclosedBlock => "Unclosed Block",
             Self::UnclosedStatement => "Unclosed Statement",
             Self::InvalidStatement => "Invalid Statement",
             Self::UnexpectedSymbol => "Unexpected Symbol",
             Self::MismatchedTypes => "Mismatched Types",
-            _ => panic!("Forgor to implement ErrorType"),
+            Self::DuplicateDefinition => "Duplicate Definition",
+            Self::ImmutableAsignment => "Immutable Asignment",
+            Self::NonExhaustiveMatch => "Non-exhaustive Match",
+            Self::IndexOutOfBounds => "Index out of Bounds",
+            Self::ArithmeticError => "Arithmetic Error",
+            Self::UnreachablePattern => "Unreachable Pattern",
+            Self::StackOverflow => "Stack Overflow",
         };
         write!(f, "{}", message)
     }
 }
+/// Whether a diagnostic fails the compilation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
 pub struct CompilerError {
     message: String,
     position: Position,
     error_type: ErrorTypes,
+    severity: Severity,
+    labels: Vec<(Position, String)>,
+    help: Vec<String>,
 }
 impl CompilerError {
     pub fn new(error_type: ErrorTypes, message: String, position: Position) -> CompilerError {
@@ -67,15 +397,139 @@ impl CompilerError {
             message,
             position,
             error_type,
+            severity: Severity::Error,
+            labels: vec![],
+            help: vec![],
         }
     }
+
+    /// A diagnostic that is reported like an error but does not fail the
+    /// compilation.
+    pub fn warning(error_type: ErrorTypes, message: String, position: Position) -> CompilerError {
+        CompilerError {
+            severity: Severity::Warning,
+            ..CompilerError::new(error_type, message, position)
+        }
+    }
+
+    /// Adds a secondary `message` pointing at related code, like the
+    /// declaration of a variable.
+    pub fn with_label(mut self, position: Position, message: String) -> CompilerError {
+        self.labels.push((position, message));
+        self
+    }
+
+    pub fn error_type(&self) -> ErrorTypes {
+        self.error_type
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn with_help(mut self, message: String) -> CompilerError {
+        self.help.push(message);
+        self
+    }
+
+    /// Renders the error with the lines of `source` it points at, underlining
+    /// the offending token with `^~~` and related code with `-`. Labels in
+    /// other files than `source` are listed as notes.
+    pub fn render(&self, source: &str, color: bool) -> String {
+        let paint = |text: &str, style: &str| {
+            if color {
+                format!("\x1b[{}m{}\x1b[0m", style, text)
+            } else {
+                text.to_string()
+            }
+        };
+        let (red, yellow, blue, bold) = ("1;31", "1;33", "1;34", "1");
+        let (title, primary) = match self.severity {
+            Severity::Error => ("Error", red),
+            Severity::Warning => ("Warning", yellow),
+        };
+        let mut annotations: Vec<(&Position, Option<&str>)> = vec![(&self.position, None)];
+        let mut notes = vec![];
+        for (position, message) in &self.labels {
+            if position.file == self.position.file {
+                annotations.push((position, Some(message)));
+            } else {
+                notes.push(format!("note: {} at {}", message, position));
+            }
+        }
+        annotations.sort_by_key(|(position, _)| (position.row, position.column));
+        let width = annotations
+            .iter()
+            .map(|(position, _)| position.row.to_string().len())
+            .max()
+            .unwrap_or(1);
+        let gutter = |row: &str| paint(&format!("{:>width$} |", row), blue);
+
+        let mut out = format!(
+            "{}: {}\n{}{} {}\n{}\n",
+            paint(&format!("{}[{}]", title, self.error_type.code()), primary),
+            paint(&format!("{}: {}", self.error_type, self.message), bold),
+            " ".repeat(width),
+            paint("-->", blue),
+            self.position,
+            gutter(""),
+        );
+        let mut last_row = None;
+        for (position, label) in annotations {
+            let line = source.lines().nth(position.row - 1).unwrap_or("");
+            if last_row != Some(position.row) {
+                if last_row.is_some_and(|row| row + 1 < position.row) {
+                    out.push_str(&format!("{}\n", paint("...", blue)));
+                }
+                out.push_str(&format!("{} {}\n", gutter(&position.row.to_string()), line));
+            }
+            last_row = Some(position.row);
+            // Columns count characters, not bytes.
+            let line_length = line.chars().count();
+            let start = (position.column - 1).min(line_length);
+            let end = if position.end_row == position.row {
+                (position.end_column - 1).clamp(start, line_length)
+            } else {
+                line_length
+            };
+            // Tabs are kept, so the underline lines up with the source line.
+            let indent: String = line
+                .chars()
+                .take(start)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let length = (end - start).max(1);
+            let underline = match label {
+                None => paint(&format!("^{}", "~".repeat(length - 1)), primary),
+                Some(label) => paint(&format!("{} {}", "-".repeat(length), label), blue),
+            };
+            out.push_str(&format!("{} {}{}\n", gutter(""), indent, underline));
+        }
+        notes.extend(self.help.iter().map(|help| format!("help: {}", help)));
+        for note in notes {
+            out.push_str(&format!(
+                "{} {} {}\n",
+                " ".repeat(width),
+                paint("=", blue),
+                note
+            ));
+        }
+        out
+    }
 }
 impl Display for CompilerError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{}: {} at {}",
-            self.error_type, self.message, self.position
+            "[{}] {}: {} at {}",
+            self.error_type.code(),
+            self.error_type,
+            self.message,
+            self.position
         )
     }
 }
@@ -83,13 +537,66 @@ impl Debug for CompilerError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{}: {} at {}",
-            self.error_type, self.message, self.position
+            "[{}] {}: {} at {}",
+            self.error_type.code(),
+            self.error_type,
+            self.message,
+            self.position
         )
     }
 }
 impl Error for CompilerError {}
 
+/// Collects the compiler errors and warnings of a whole compilation, so they
+/// can be reported together instead of one per run.
+#[derive(Default)]
+pub struct Diagnostics {
+    errors: RefCell<Vec<CompilerError>>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Diagnostics {
+        Diagnostics::default()
+    }
+
+    pub fn report(&self, error: CompilerError) {
+        self.errors.borrow_mut().push(error);
+    }
+
+    /// Records the compiler error of `result` and continues with `None`.
+    /// Any other error, like failing to read the source, is passed on.
+    pub fn recover<T>(&self, result: Res<T>) -> Res<Option<T>> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(error) => {
+                self.report(*error.downcast::<CompilerError>()?);
+                Ok(None)
+            }
+        }
+    }
+
+    /// The number of collected errors, warnings are not counted.
+    pub fn len(&self) -> usize {
+        self.errors
+            .borrow()
+            .iter()
+            .filter(|error| error.severity == Severity::Error)
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The collected errors and warnings ordered by their position in the
+    /// source.
+    pub fn into_errors(self) -> Vec<CompilerError> {
+        let mut errors = self.errors.into_inner();
+        errors.sort_by(|a, b| a.position.cmp(&b.position));
+        errors
+    }
+}
+
 pub struct ErrorHandler {
     exec_file: String,
 }
@@ -136,9 +643,10 @@ pub trait TokenVariant: AToAny {
     fn token_type(&self) -> &str;
 }
 //CONTROL   ////////////////////////////////////
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ControlVariants {
     Comma,            // ","
+    Dot,              // "."
     Semicolon,        // ";"
     OpenParanthesis,  // "("
     CloseParanthesis, // ")"
@@ -154,6 +662,7 @@ pub enum ControlVariants {
     GreaterEqual,     // ">="
     Asign,            // "="
     Equal,            // "=="
+    FatArrow,         // "=>"
     Or,               // "||"
     And,              // "&&"
     BitOr,            // "|"
@@ -173,6 +682,7 @@ impl TokenVariant for ControlVariants {
     {
         match value {
             "," => Some(Self::Comma),
+            "." => Some(Self::Dot),
             ";" => Some(Self::Semicolon),
             "(" => Some(Self::OpenParanthesis),
             ")" => Some(Self::CloseParanthesis),
@@ -188,6 +698,7 @@ impl TokenVariant for ControlVariants {
             ">=" => Some(Self::GreaterEqual),
             "=" => Some(Self::Asign),
             "==" => Some(Self::Equal),
+            "=>" => Some(Self::FatArrow),
             "||" => Some(Self::Or),
             "&&" => Some(Self::And),
             "|" => Some(Self::BitOr),
@@ -206,6 +717,7 @@ impl TokenVariant for ControlVariants {
     fn to_string(&self) -> String {
         match self {
             Self::Comma => String::from(","),
+            Self::Dot => String::from("."),
             Self::Semicolon => String::from(";"),
             Self::OpenParanthesis => String::from("("),
             Self::CloseParanthesis => String::from(")"),
@@ -221,6 +733,7 @@ impl TokenVariant for ControlVariants {
             Self::GreaterEqual => String::from(">="),
             Self::Asign => String::from("="),
             Self::Equal => String::from("=="),
+            Self::FatArrow => String::from("=>"),
             Self::Or => String::from("||"),
             Self::And => String::from("&&"),
             Self::BitOr => String::from("|"),
@@ -232,7 +745,6 @@ impl TokenVariant for ControlVariants {
             Self::Divide => String::from("/"),
             Self::Not => String::from("!"),
             Self::NotEqual => String::from("!="),
-            _ => panic!("Forgor to implement ControlTokenVariant"),
         }
     }
 
@@ -242,10 +754,11 @@ impl TokenVariant for ControlVariants {
 }
 ////////////////////////////////////////////////
 //Word   ////////////////////////////////////
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum WordVariants {
     Func,     // "func"
-    Num,      // "num"
+    Int,      // "int"
+    Float,    // "float"
     Str,      // "str"
     Bool,     // "bool"
     Args,     // "args"
@@ -267,7 +780,8 @@ impl TokenVariant for WordVariants {
     {
         match value {
             "func" => Some(Self::Func),
-            "num" => Some(Self::Num),
+            "int" => Some(Self::Int),
+            "float" => Some(Self::Float),
             "str" => Some(Self::Str),
             "bool" => Some(Self::Bool),
             "args" => Some(Self::Args),
@@ -288,7 +802,8 @@ impl TokenVariant for WordVariants {
     fn to_string(&self) -> String {
         match self {
             Self::Func => String::from("func"),
-            Self::Num => String::from("num"),
+            Self::Int => String::from("int"),
+            Self::Float => String::from("float"),
             Self::Str => String::from("str"),
             Self::Bool => String::from("bool"),
             Self::Args => String::from("args"),
@@ -302,7 +817,6 @@ impl TokenVariant for WordVariants {
             Self::Break => String::from("break"),
             Self::Match => String::from("match"),
             Self::Def => String::from("def"),
-            _ => panic!("Forgor to implement WordTokenVariants"),
         }
     }
 
@@ -316,11 +830,24 @@ impl TokenVariant for WordVariants {
 pub struct NameVariants {
     name: String,
 }
+impl NameVariants {
+    /// Names start with a letter or '_' followed by letters, digits and '_'.
+    pub fn is_valid(value: &str) -> bool {
+        let mut chars = value.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+}
 impl TokenVariant for NameVariants {
     fn new(value: &str) -> Option<Self>
     where
         Self: Sized,
     {
+        if !Self::is_valid(value) {
+            return None;
+        }
         Some(Self {
             name: value.to_string(),
         })
@@ -340,6 +867,11 @@ impl TokenVariant for NameVariants {
 pub struct StringVariants {
     str: String,
 }
+impl StringVariants {
+    pub fn value(&self) -> &str {
+        &self.str[1..self.str.len() - 1]
+    }
+}
 impl TokenVariant for StringVariants {
     fn new(value: &str) -> Option<Self>
     where
@@ -359,11 +891,74 @@ impl TokenVariant for StringVariants {
     }
 }
 ////////////////////////////////////////////////
+//Interpolation   ////////////////////////////////////
+#[derive(Clone)]
+pub enum InterpolationPart {
+    Text(String),
+    /// The tokens between `{` and `}` and the `Position` of the first one.
+    Expression {
+        tokens: Vec<Token>,
+        position: Position,
+    },
+}
+/// A string literal with embedded expressions like `"Hello, {name}!"`.
+#[derive(Clone)]
+pub struct InterpolationVariants {
+    parts: Vec<InterpolationPart>,
+}
+impl InterpolationVariants {
+    pub fn from_parts(parts: Vec<InterpolationPart>) -> Self {
+        Self { parts }
+    }
+
+    pub fn parts(&self) -> &[InterpolationPart] {
+        &self.parts
+    }
+}
+impl TokenVariant for InterpolationVariants {
+    fn new(value: &str) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        Some(Self {
+            parts: vec![InterpolationPart::Text(value.to_string())],
+        })
+    }
+
+    fn to_string(&self) -> String {
+        let parts = self
+            .parts
+            .iter()
+            .map(|part| match part {
+                InterpolationPart::Text(text) => text.replace('{', "{{").replace('}', "}}"),
+                InterpolationPart::Expression { tokens, .. } => format!(
+                    "{{{}}}",
+                    tokens
+                        .iter()
+                        .map(|token| token.token.to_string())
+                        .collect::<Vec<String>>()
+                        .join(" ")
+                ),
+            })
+            .collect::<String>();
+        format!("\"{}\"", parts)
+    }
+
+    fn token_type(&self) -> &str {
+        "str"
+    }
+}
+////////////////////////////////////////////////
 //Boolean   ////////////////////////////////////
 #[derive(Clone)]
 pub struct BooleanVariants {
     bool: bool,
 }
+impl BooleanVariants {
+    pub fn value(&self) -> bool {
+        self.bool
+    }
+}
 impl TokenVariant for BooleanVariants {
     fn new(value: &str) -> Option<Self>
     where
@@ -374,7 +969,7 @@ impl TokenVariant for BooleanVariants {
             "false" => Some(false),
             _ => None,
         }
-        .and_then(|b| Some(Self { bool: b }))
+        .map(|b| Self { bool: b })
     }
 
     fn to_string(&self) -> String {
@@ -387,17 +982,97 @@ impl TokenVariant for BooleanVariants {
 }
 ////////////////////////////////////////////////
 //Number   ////////////////////////////////////
+/// Int literals are unsigned, their sign is the unary `-` in front of them.
+/// They may be up to 2^63, which only fits into an int as `-2^63`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Number {
+    Int(u64),
+    Float(f64),
+}
 #[derive(Clone)]
 pub struct NumberVariants {
-    num: f64,
+    num: Number,
     str: String,
 }
+impl NumberVariants {
+    pub fn value(&self) -> Number {
+        self.num
+    }
+
+    /// Parses a number literal of the grammar
+    ///
+    /// ```text
+    /// number  = decimal | "0x" digits | "0o" digits | "0b" digits
+    /// decimal = digits ["." digits] [("e" | "E") ["+" | "-"] digits]
+    /// digits  = digit {["_"] digit}
+    /// ```
+    ///
+    /// where the digits of `0x`, `0o` and `0b` literals are the ones of base
+    /// 16, 8 and 2 and a single `_` may separate any two digits. Literals with
+    /// a fraction or an exponent are floats, all others ints.
+    pub fn parse(value: &str) -> Result<Number, String> {
+        for (prefix, radix) in [("0x", 16), ("0o", 8), ("0b", 2)] {
+            if let Some(digits) = value.strip_prefix(prefix) {
+                return Self::int(&Self::digits(digits, radix)?, radix);
+            }
+        }
+        let (mantissa, exponent) = match value.find(['e', 'E']) {
+            Some(e) => (&value[..e], Some(&value[e + 1..])),
+            None => (value, None),
+        };
+        if exponent.is_none() && !mantissa.contains('.') {
+            return Self::int(&Self::digits(mantissa, 10)?, 10);
+        }
+        let mut literal = match mantissa.split_once('.') {
+            Some((whole, fraction)) => {
+                format!(
+                    "{}.{}",
+                    Self::digits(whole, 10)?,
+                    Self::digits(fraction, 10)?
+                )
+            }
+            None => Self::digits(mantissa, 10)?,
+        };
+        if let Some(exponent) = exponent {
+            let (sign, digits) = match exponent.strip_prefix(['+', '-']) {
+                Some(digits) => (&exponent[..1], digits),
+                None => ("", exponent),
+            };
+            literal = format!("{}e{}{}", literal, sign, Self::digits(digits, 10)?);
+        }
+        match literal.parse::<f64>() {
+            Ok(num) if num.is_finite() => Ok(Number::Float(num)),
+            _ => Err(String::from("the number is too large for a float")),
+        }
+    }
+
+    fn int(digits: &str, radix: u32) -> Result<Number, String> {
+        match u64::from_str_radix(digits, radix) {
+            Ok(int) if int <= i64::MIN.unsigned_abs() => Ok(Number::Int(int)),
+            _ => Err(String::from("the number is too large for an int")),
+        }
+    }
+
+    /// Checks `digits` of base `radix` and strips the `_` separators.
+    fn digits(digits: &str, radix: u32) -> Result<String, String> {
+        if digits.is_empty() {
+            return Err(String::from("expected digits"));
+        }
+        if digits.starts_with('_') || digits.ends_with('_') || digits.contains("__") {
+            return Err(String::from("'_' may only separate two digits"));
+        }
+        match digits.chars().find(|c| *c != '_' && !c.is_digit(radix)) {
+            Some(c) => Err(format!("'{}' is not a digit of base {}", c, radix)),
+            None => Ok(digits.replace('_', "")),
+        }
+    }
+}
 impl TokenVariant for NumberVariants {
     fn new(value: &str) -> Option<Self>
     where
         Self: Sized,
     {
-        match value.parse() {
+        match Self::parse(value) {
             Ok(num) => Some(Self {
                 num,
                 str: String::from(value),
@@ -411,14 +1086,42 @@ impl TokenVariant for NumberVariants {
     }
 
     fn token_type(&self) -> &str {
-        "num"
+        match self.num {
+            Number::Int(_) => "int",
+            Number::Float(_) => "float",
+        }
     }
 }
 ////////////////////////////////////////////////
+//Invalid   ////////////////////////////////////
+/// Stands in for source text the tokenizer already reported as malformed, so
+/// the parser can tell its own errors about it apart as follow-ups.
+#[derive(Clone)]
+pub struct InvalidVariants {
+    str: String,
+}
+impl TokenVariant for InvalidVariants {
+    fn new(value: &str) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        Some(Self {
+            str: value.to_string(),
+        })
+    }
+
+    fn to_string(&self) -> String {
+        self.str.clone()
+    }
 
-pub fn is_enum_token_variant(token_value: String) -> bool {
-    ControlVariants::new(&token_value).is_some() || WordVariants::new(&token_value).is_some()
+    fn token_type(&self) -> &str {
+        "invalid"
+    }
 }
+////////////////////////////////////////////////
+
+/// Length in characters of the longest `ControlVariants` spelling.
+pub const MAX_CONTROL_LENGTH: usize = 2;
 
 #[derive(Clone)]
 pub enum TokenTypes {
@@ -426,40 +1129,29 @@ pub enum TokenTypes {
     Word(WordVariants),
     Name(NameVariants),
     String(StringVariants),
+    Interpolation(InterpolationVariants),
     Number(NumberVariants),
     Boolean(BooleanVariants),
+    Invalid(InvalidVariants),
 }
 impl TokenTypes {
-    fn new(token_value: &str, is_in_string: bool) -> Self {
-        match ControlVariants::new(&token_value) {
-            Some(t) => Self::Control(t),
-            None => match WordVariants::new(&token_value) {
-                Some(t) => Self::Word(t),
-                None => {
-                    if is_in_string {
-                        match StringVariants::new(&token_value) {
-                            Some(t) => Self::String(t),
-                            None => panic!(
-                                "Somehow the StringTokenVariant failed at '{}'",
-                                &token_value
-                            ),
-                        }
-                    } else {
-                        match BooleanVariants::new(&token_value) {
-                            Some(t) => Self::Boolean(t),
-                            None => match NumberVariants::new(&token_value) {
-                                Some(t) => Self::Number(t),
-                                None => match NameVariants::new(&token_value) {
-                                    Some(t) => Self::Name(t),
-                                    None => panic!(
-                                        "Somehow the NameTokenVariant failed at '{}'",
-                                        &token_value
-                                    ),
-                                },
-                            },
-                        }
-                    }
-                }
+    /// Returns `None` if `token_value` is neither a symbol, keyword or literal
+    /// nor a valid name.
+    fn new(token_value: &str, is_in_string: bool) -> Option<Self> {
+        if is_in_string {
+            return StringVariants::new(token_value).map(Self::String);
+        }
+        match ControlVariants::new(token_value) {
+            Some(t) => Some(Self::Control(t)),
+            None => match WordVariants::new(token_value) {
+                Some(t) => Some(Self::Word(t)),
+                None => match BooleanVariants::new(token_value) {
+                    Some(t) => Some(Self::Boolean(t)),
+                    None => match NumberVariants::new(token_value) {
+                        Some(t) => Some(Self::Number(t)),
+                        None => NameVariants::new(token_value).map(Self::Name),
+                    },
+                },
             },
         }
     }
@@ -470,22 +1162,21 @@ impl TokenTypes {
             Self::Word(w) => w,
             Self::Name(na) => na,
             Self::String(s) => s,
+            Self::Interpolation(i) => i,
             Self::Number(nu) => nu,
             Self::Boolean(b) => b,
+            Self::Invalid(i) => i,
         }
     }
 
     pub fn is_literal(&self) -> bool {
-        match self {
-            TokenTypes::Number(_) => true,
-            TokenTypes::Boolean(_) => true,
-            TokenTypes::String(_) => true,
-            _ => false,
-        }
-    }
-
-    pub fn to_string(&self) -> String {
-        self.as_token_variant().to_string()
+        matches!(
+            self,
+            TokenTypes::Number(_)
+                | TokenTypes::Boolean(_)
+                | TokenTypes::String(_)
+                | TokenTypes::Interpolation(_)
+        )
     }
 
     pub fn token_type(&self) -> &str {
@@ -493,6 +1184,12 @@ impl TokenTypes {
     }
 }
 
+impl Display for TokenTypes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_token_variant().to_string())
+    }
+}
+
 #[derive(Clone)]
 pub struct Token {
     pub token: TokenTypes,
@@ -503,16 +1200,178 @@ impl Debug for Token {
         write!(
             f,
             "{{ token: '{}', position: '{}' }}",
-            self.token.to_string(),
-            self.position
+            self.token, self.position
         )
     }
 }
 impl Token {
-    pub fn new(token_value: String, pos: Position, is_in_string: bool) -> Token {
+    pub fn new(token_value: String, pos: Position, is_in_string: bool) -> Option<Token> {
+        TokenTypes::new(&token_value, is_in_string).map(|token| Token {
+            token,
+            position: pos,
+        })
+    }
+
+    /// A placeholder for malformed source text that was already reported.
+    pub fn invalid(token_value: &str, pos: Position) -> Token {
         Token {
-            token: TokenTypes::new(&token_value, is_in_string),
+            token: TokenTypes::Invalid(InvalidVariants {
+                str: token_value.to_string(),
+            }),
             position: pos,
         }
     }
+
+    /// Whether the token, or an expression embedded in it, is malformed.
+    pub fn is_invalid(&self) -> bool {
+        match &self.token {
+            TokenTypes::Invalid(_) => true,
+            TokenTypes::Interpolation(interpolation) => {
+                interpolation.parts().iter().any(|part| match part {
+                    InterpolationPart::Expression { tokens, .. } => {
+                        tokens.iter().any(Token::is_invalid)
+                    }
+                    InterpolationPart::Text(_) => false,
+                })
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(start: (usize, usize), end: (usize, usize)) -> Position {
+        Position::span(String::from("test"), start, end)
+    }
+
+    #[test]
+    fn render_underlines_the_span() {
+        let error = CompilerError::new(
+            ErrorTypes::UnexpectedSymbol,
+            String::from("expected a variable name, but found no 'yy' in this scope"),
+            span((1, 22), (1, 24)),
+        );
+        assert_eq!(
+            error.render("str s = \"äöü\"; print(yy);\n", false),
+            "Error[E0005]: Unexpected Symbol: expected a variable name, but found no 'yy' in this scope
+ --> test:1:22
+  |
+1 | str s = \"äöü\"; print(yy);
+  |                      ^~
+"
+        );
+    }
+
+    #[test]
+    fn render_labels_and_help() {
+        let error = CompilerError::new(
+            ErrorTypes::DuplicateDefinition,
+            String::from("'x' is already defined in this scope"),
+            span((10, 6), (10, 7)),
+        )
+        .with_label(span((8, 5), (8, 6)), String::from("previous definition"))
+        .with_label(
+            Position::span(String::from("other"), (1, 1), (1, 2)),
+            String::from("elsewhere"),
+        )
+        .with_help(String::from("rename it"));
+        let source = "\n\n\n\n\n\n\nint x = 1;\n\n\tint x = 2;\n";
+        assert_eq!(
+            error.render(source, false),
+            "Error[E0007]: Duplicate Definition: 'x' is already defined in this scope
+  --> test:10:6
+   |
+ 8 | int x = 1;
+   |     - previous definition
+...
+10 | \tint x = 2;
+   | \t    ^
+   = note: elsewhere at other:1:1
+   = help: rename it
+"
+        );
+    }
+
+    #[test]
+    fn render_colors() {
+        let error = CompilerError::new(
+            ErrorTypes::UnexpectedSymbol,
+            String::from("unexpected symbol"),
+            span((1, 1), (1, 2)),
+        );
+        let colored = error.render("_", true);
+        assert!(colored.starts_with("\x1b[1;31mError[E0005]\x1b[0m: "));
+        assert!(colored.contains("\x1b[1;31m^\x1b[0m"));
+    }
+
+    #[test]
+    fn error_codes_round_trip() {
+        for error_type in ErrorTypes::ALL {
+            assert_eq!(ErrorTypes::from_code(error_type.code()), Some(error_type));
+        }
+        assert_eq!(
+            ErrorTypes::from_code("e0001"),
+            Some(ErrorTypes::UnclosedString)
+        );
+        assert_eq!(ErrorTypes::from_code("E0000"), None);
+        assert_eq!(ErrorTypes::from_code("0001"), None);
+    }
+
+    #[test]
+    fn error_codes_are_unique() {
+        let mut codes: Vec<&str> = ErrorTypes::ALL.iter().map(ErrorTypes::code).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), ErrorTypes::ALL.len());
+    }
+
+    #[test]
+    fn every_error_is_explained_with_an_example() {
+        for error_type in ErrorTypes::ALL {
+            let explanation = error_type.explanation();
+            assert!(
+                explanation.contains("Erroneous code example:\n\n    "),
+                "{}",
+                error_type.code()
+            );
+        }
+    }
+
+    #[test]
+    fn render_warnings() {
+        let warning = CompilerError::warning(
+            ErrorTypes::UnreachablePattern,
+            String::from("unreachable match arm"),
+            span((1, 1), (1, 2)),
+        );
+        assert!(warning.render("_", false).starts_with("Warning[E0013]: "));
+        let colored = warning.render("_", true);
+        assert!(colored.starts_with("\x1b[1;33mWarning[E0013]\x1b[0m: "));
+        assert!(colored.contains("\x1b[1;33m^\x1b[0m"));
+    }
+
+    #[test]
+    fn diagnostics_count_only_errors() {
+        let diagnostics = Diagnostics::new();
+        diagnostics.report(CompilerError::new(
+            ErrorTypes::UnclosedString,
+            String::new(),
+            span((2, 1), (2, 2)),
+        ));
+        diagnostics.report(CompilerError::warning(
+            ErrorTypes::UnreachablePattern,
+            String::new(),
+            span((1, 1), (1, 2)),
+        ));
+        assert_eq!(diagnostics.len(), 1);
+        let codes: Vec<&str> = diagnostics
+            .into_errors()
+            .iter()
+            .map(|error| error.error_type().code())
+            .collect();
+        assert_eq!(codes, ["E0013", "E0001"]);
+    }
 }
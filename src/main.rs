@@ -1,5 +1,9 @@
-use ops::{ErrorHandler, Res};
-use std::{ffi::OsStr, fs::File, io::BufReader, path::Path};
+use ops::{CompilerError, Diagnostics, ErrorHandler, ErrorTypes, Res, Severity};
+use std::{
+    ffi::OsStr,
+    io::{BufReader, IsTerminal},
+    path::Path,
+};
 
 mod tokenizer;
 use tokenizer::tokenizer;
@@ -7,6 +11,9 @@ use tokenizer::tokenizer;
 mod parser;
 use parser::Scope;
 
+mod interpreter;
+use interpreter::{with_stack, Interpreter};
+
 const FILE_EXTENSION: &str = "nop";
 
 fn help_print(exec_path: &str) {
@@ -14,18 +21,21 @@ fn help_print(exec_path: &str) {
         "
     Usage:
         {} source_file [OPTIONS]
+        {} --explain CODE
     
     Available Options include:
         -h  --help       Prints this help message.
-        -r  --run        Run the compiled executable.
+        -r  --run        Run the program with the interpreter.
+        -e  --explain    Explains the error with the given code, like E0001.
     ",
-        exec_path
+        exec_path, exec_path
     );
 }
 
 enum CommandLineArguments {
     HelpFlag,
     RunFlag,
+    ExplainOption,
 }
 
 impl CommandLineArguments {
@@ -33,34 +43,48 @@ impl CommandLineArguments {
         match arg {
             x if ["-h", "--help"].contains(&x) => Ok(CommandLineArguments::HelpFlag),
             x if ["-r", "--run"].contains(&x) => Ok(CommandLineArguments::RunFlag),
+            x if ["-e", "--explain"].contains(&x) => Ok(CommandLineArguments::ExplainOption),
             _ => Err(format!("{} is no valid option.", arg).into()),
         }
     }
 }
 
-struct CLI {
+struct Cli {
     exec_file: String,
     source_file: String,
     help: bool,
     run: bool,
+    explain: Option<String>,
 }
 
-impl CLI {
-    fn new(exec_file: String) -> CLI {
-        CLI {
+impl Cli {
+    fn new(exec_file: String) -> Cli {
+        Cli {
             exec_file,
             source_file: String::from(""),
             help: false,
             run: false,
+            explain: None,
+        }
+    }
+
+    /// Takes the error code following the `--explain` flag at `flag`.
+    fn parse_explain(&mut self, args: &[String], flag: usize, err: &ErrorHandler) -> Res<()> {
+        match args.get(flag + 1) {
+            Some(code) => {
+                self.explain = Some(code.clone());
+                Ok(())
+            }
+            None => err.helpful_err("No error code specified."),
         }
     }
 
-    fn from_argv(&mut self, err: &ErrorHandler) -> Res<()> {
+    fn parse_argv(&mut self, err: &ErrorHandler) -> Res<()> {
         let mut args: Vec<String> = std::env::args().collect();
-        if args.len() > 0 && args[0].contains(&self.exec_file) {
+        if !args.is_empty() && args[0].contains(&self.exec_file) {
             args.drain(0..1);
         }
-        if args.len() < 1 {
+        if args.is_empty() {
             return err.helpful_err("No source file specified.");
         }
 
@@ -71,7 +95,10 @@ impl CLI {
                     self.help = true;
                     return Ok(());
                 }
-                _ => {}
+                Ok(CommandLineArguments::ExplainOption) => {
+                    return self.parse_explain(&args, 0, err)
+                }
+                _ => (),
             }
             return ErrorHandler::err(&format!("Source file '{}' does not exist.", args[0]));
         }
@@ -102,6 +129,10 @@ impl CLI {
             match arg {
                 CommandLineArguments::HelpFlag => self.help = true,
                 CommandLineArguments::RunFlag => self.run = true,
+                CommandLineArguments::ExplainOption => {
+                    self.parse_explain(&args, i, err)?;
+                    i += 1;
+                }
             }
             i += 1;
         }
@@ -109,22 +140,68 @@ impl CLI {
     }
 }
 
+/// Diagnostics are coloured on a terminal, unless `NO_COLOR` is set.
+fn use_color() -> bool {
+    std::io::stderr().is_terminal()
+        && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+}
+
+/// Prints `diagnostics` with their source snippets and exits with a failure
+/// if any of them is an error. `stage` names what failed, like "Compilation".
+fn report(diagnostics: &[CompilerError], source: &str, exec_name: &str, stage: &str) {
+    let color = use_color();
+    for diagnostic in diagnostics {
+        eprintln!("{}", diagnostic.render(source, color));
+    }
+    let errors: Vec<&CompilerError> = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity() == Severity::Error)
+        .collect();
+    if let Some(error) = errors.first() {
+        eprintln!("{} failed with {} error(s).", stage, errors.len());
+        eprintln!(
+            "For more information about an error, try `{} --explain {}`.",
+            exec_name,
+            error.error_type().code()
+        );
+        std::process::exit(1);
+    }
+}
+
+fn explain(code: &str) -> Res<()> {
+    match ErrorTypes::from_code(code) {
+        Some(error_type) => {
+            println!(
+                "{}: {}\n\n{}",
+                error_type.code(),
+                error_type,
+                error_type.explanation()
+            );
+            Ok(())
+        }
+        None => ErrorHandler::err(&format!("'{}' is no known error code.", code)),
+    }
+}
+
 fn main() -> Res<()> {
     let exec_name = std::env::current_exe()?
         .file_name()
-        .unwrap_or_else(|| OsStr::new(""))
+        .unwrap_or(OsStr::new(""))
         .to_str()
-        .ok_or_else(|| "Reading executable name failed.")?
+        .ok_or("Reading executable name failed.")?
         .to_string();
     let err = ErrorHandler::new(exec_name.clone());
-    let mut args = CLI::new(exec_name.clone());
-    args.from_argv(&err)?;
+    let mut args = Cli::new(exec_name.clone());
+    args.parse_argv(&err)?;
     if args.help {
         help_print(&exec_name);
         return Ok(());
     }
-    let f = match File::open(&args.source_file) {
-        Ok(f) => f,
+    if let Some(code) = &args.explain {
+        return explain(code);
+    }
+    let source = match std::fs::read_to_string(&args.source_file) {
+        Ok(source) => source,
         Err(e) => {
             return ErrorHandler::err(&format!(
                 "Could not open source file '{}'. {}",
@@ -132,8 +209,12 @@ fn main() -> Res<()> {
             ))
         }
     };
-    let f = BufReader::new(f);
-    let tokens = tokenizer(f, &args.source_file)?;
+    let diagnostics = Diagnostics::new();
+    let tokens = tokenizer(
+        BufReader::new(source.as_bytes()),
+        &args.source_file,
+        &diagnostics,
+    )?;
     // for token in tokens {
     //     println!(
     //         "{} Token with value: '{}' at {}",
@@ -142,6 +223,25 @@ fn main() -> Res<()> {
     //         token.position
     //     )
     // }
-    Scope::new().parse_block(tokens)?;
+    let program = Scope::new(&diagnostics).parse_block(tokens)?;
+    report(
+        &diagnostics.into_errors(),
+        &source,
+        &exec_name,
+        "Compilation",
+    );
+    if args.run {
+        let result = with_stack(|| {
+            Interpreter::new().run(&program).map_err(|error| {
+                match error.downcast::<CompilerError>() {
+                    Ok(error) => Ok(error),
+                    Err(error) => Err(error.to_string()),
+                }
+            })
+        })?;
+        if let Err(error) = result {
+            report(&[*error?], &source, &exec_name, "Execution");
+        }
+    }
     Ok(())
 }
@@ -1,25 +1,27 @@
-use std::{any::Any, collections::HashMap, fmt::Debug};
+use std::{collections::HashMap, fmt::Debug};
 
 use ops::{
-    ControlVariants, ErrorHandler, ErrorTypes, Position, Res, Token, TokenTypes, TokenVariant,
-    WordVariants,
+    ast::{
+        comparison, float_arithmetic, float_to_int, int_arithmetic, Block, Expression,
+        ExpressionKind, Literal, Statement, StatementKind, Type,
+    },
+    CompilerError, ControlVariants, Diagnostics, ErrorHandler, ErrorTypes, InterpolationPart,
+    InterpolationVariants, Number, Position, Res, Token, TokenTypes, TokenVariant, WordVariants,
 };
 
-use crate::tokenizer;
-
 pub struct Variable {
     name: String,
     position: Position,
-    var_type: String,
-    value: String,
+    var_type: Type,
+    constant: Option<Literal>,
 }
 impl Variable {
-    fn new(var_type: String, name: String, position: Position, value: String) -> Variable {
+    fn new(var_type: Type, name: String, position: Position) -> Variable {
         Variable {
             name,
             position,
             var_type,
-            value,
+            constant: None,
         }
     }
 }
@@ -27,8 +29,8 @@ impl Debug for Variable {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Variable {{ '{}' at {} with type {} and value '{}' }}",
-            self.name, self.position, self.var_type, self.value
+            "Variable {{ '{}' at {} with type {} and constant value {:?} }}",
+            self.name, self.position, self.var_type, self.constant
         )
     }
 }
@@ -37,159 +39,385 @@ pub struct Function {
     name: String,
     position: Position,
     parameter: Vec<Variable>,
-    expression: String,
+    return_type: Type,
+    is_inbuild: bool,
+    /// Forward declarations are defined later in the same scope.
+    is_defined: bool,
 }
 impl Function {
     fn new(
         name: String,
         position: Position,
         parameter: Vec<Variable>,
-        expression: String,
+        return_type: Type,
     ) -> Function {
         Function {
             name,
             position,
             parameter,
-            expression,
+            return_type,
+            is_inbuild: false,
+            is_defined: true,
+        }
+    }
+
+    /// Inbuild functions accept any arguments, the interpreter checks them.
+    fn inbuild(name: &str, position: Position, return_type: Type) -> Function {
+        Function {
+            name: name.to_string(),
+            position,
+            parameter: vec![],
+            return_type,
+            is_inbuild: true,
+            is_defined: true,
+        }
+    }
+}
+impl Debug for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Function {{ '{}' at {} with parameters {:?} and return type {} }}",
+            self.name, self.position, self.parameter, self.return_type
+        )
+    }
+}
+
+pub struct Record {
+    name: String,
+    position: Position,
+    fields: Vec<(String, Type)>,
+}
+impl Record {
+    fn new(name: String, position: Position, fields: Vec<(String, Type)>) -> Record {
+        Record {
+            name,
+            position,
+            fields,
         }
     }
+
+    fn field_type(&self, field: &str) -> Option<&Type> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, field_type)| field_type)
+    }
+}
+impl Debug for Record {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Record {{ '{}' at {} with fields {:?} }}",
+            self.name, self.position, self.fields
+        )
+    }
 }
 
-pub struct Scope {
+pub struct Scope<'a> {
+    parent: Option<&'a Scope<'a>>,
     variables: HashMap<String, Variable>,
     functions: HashMap<String, Function>,
+    records: HashMap<String, Record>,
+    is_loop: bool,
+    return_type: Option<Type>,
+    diagnostics: &'a Diagnostics,
 }
 
-impl Scope {
-    pub fn new() -> Self {
-        let inbuild_functions: [(String, Function); 1] = [(
-            String::from("print"),
-            Function::new(
+impl<'a> Scope<'a> {
+    /// Creates the global scope, errors of statements are reported to
+    /// `diagnostics` while parsing goes on.
+    pub fn new(diagnostics: &'a Diagnostics) -> Self {
+        let inbuild_functions: [(String, Function); 2] = [
+            (
                 String::from("print"),
-                Position::new(file!().to_string(), line!() as usize, column!() as usize),
-                vec![],
-                "print".to_string(),
+                Function::inbuild(
+                    "print",
+                    Position::new(file!().to_string(), line!() as usize, column!() as usize),
+                    Type::Void,
+                ),
+            ),
+            (
+                String::from("len"),
+                Function::inbuild(
+                    "len",
+                    Position::new(file!().to_string(), line!() as usize, column!() as usize),
+                    Type::Int,
+                ),
             ),
-        )];
+        ];
         Self {
+            parent: None,
             variables: HashMap::new(),
             functions: HashMap::from_iter(inbuild_functions),
+            records: HashMap::new(),
+            is_loop: false,
+            return_type: None,
+            diagnostics,
         }
     }
 
+    fn child(&self) -> Scope<'_> {
+        Scope {
+            parent: Some(self),
+            variables: HashMap::new(),
+            functions: HashMap::new(),
+            records: HashMap::new(),
+            is_loop: false,
+            return_type: None,
+            diagnostics: self.diagnostics,
+        }
+    }
+
+    /// Loops do not reach across function bodies.
+    fn is_in_loop(&self) -> bool {
+        self.is_loop
+            || (self.return_type.is_none() && self.parent.is_some_and(|parent| parent.is_in_loop()))
+    }
+
+    fn function_return_type(&self) -> Option<&Type> {
+        self.return_type
+            .as_ref()
+            .or_else(|| self.parent.and_then(|parent| parent.function_return_type()))
+    }
+
+    fn get_variable(&self, name: &str) -> Option<&Variable> {
+        self.variables
+            .get(name)
+            .or_else(|| self.parent.and_then(|parent| parent.get_variable(name)))
+    }
+
+    fn get_function(&self, name: &str) -> Option<&Function> {
+        self.functions
+            .get(name)
+            .or_else(|| self.parent.and_then(|parent| parent.get_function(name)))
+    }
+
+    fn get_record(&self, name: &str) -> Option<&Record> {
+        self.records
+            .get(name)
+            .or_else(|| self.parent.and_then(|parent| parent.get_record(name)))
+    }
+
+    /// Resolves the type at the start of `tokens` together with the number of
+    /// tokens it spans. Besides the inbuild types and the records defined via
+    /// `def` this includes arrays of them written as `type[]`.
+    fn parse_type(&self, tokens: &[Token]) -> Option<(Type, usize)> {
+        let mut var_type = match &tokens.first()?.token {
+            TokenTypes::Name(name) => self
+                .get_record(&name.to_string())
+                .map(|record| Type::Record(record.name.clone()))?,
+            tok => Type::from_token(tok)?,
+        };
+        let mut length = 1;
+        while let [Token {
+            token: TokenTypes::Control(ControlVariants::OpenSquare),
+            ..
+        }, Token {
+            token: TokenTypes::Control(ControlVariants::CloseSquare),
+            ..
+        }, ..] = &tokens[length..]
+        {
+            var_type = Type::Array(Box::new(var_type));
+            length += 2;
+        }
+        Some((var_type, length))
+    }
+
+    /// Whether all of `tokens` form exactly one type.
+    fn is_type(&self, tokens: &[Token]) -> bool {
+        matches!(self.parse_type(tokens), Some((_, length)) if length == tokens.len())
+    }
+
     fn create_variable(
         &mut self,
-        variable_type: String,
+        variable_type: Type,
         variable_name: String,
         name_position: Position,
-    ) {
-        self.variables.insert(
-            variable_name.clone(),
-            Variable::new(
-                variable_type,
-                variable_name,
+        constant: Option<Literal>,
+    ) -> Res<()> {
+        if let Some(variable) = self.variables.get(&variable_name) {
+            return Err(CompilerError::new(
+                ErrorTypes::DuplicateDefinition,
+                format!("'{}' is already defined in this scope", variable_name),
                 name_position,
-                String::from(""),
-            ),
-        );
+            )
+            .with_label(
+                variable.position.clone(),
+                format!("previous definition of '{}' here", variable_name),
+            )
+            .into());
+        }
+        let mut variable = Variable::new(variable_type, variable_name.clone(), name_position);
+        variable.constant = constant;
+        self.variables.insert(variable_name, variable);
+        Ok(())
     }
 
-    fn asign_variable(
-        &mut self,
-        variable_name: Token,
-        value_type: String,
-        value: String,
-    ) -> Res<()> {
-        match self.variables.get_mut(&variable_name.token.to_string()) {
-            Some(var) => {
-                if var.var_type == value_type {
-                    var.value = value;
-                    Ok(())
-                } else {
-                    ErrorHandler::compiler_err(
-                        ErrorTypes::MismatchedTypes,
-                        format!(
-                            "expected {}-type but found a {}-type",
-                            var.var_type, value_type,
-                        ),
-                        variable_name.position,
-                    )
-                }
+    fn expect_type(expected: &Type, value: &Expression) -> Res<()> {
+        if expected.accepts(&value.value_type) {
+            Ok(())
+        } else {
+            ErrorHandler::compiler_err(
+                ErrorTypes::MismatchedTypes,
+                format!(
+                    "expected {}-type but found a {}-type",
+                    expected, value.value_type,
+                ),
+                value.position.clone(),
+            )
+        }
+    }
+
+    fn constant_asignment(constant: &Variable, position: Position) -> CompilerError {
+        CompilerError::new(
+            ErrorTypes::ImmutableAsignment,
+            format!("cannot asign to constant '{}'", constant.name),
+            position,
+        )
+        .with_label(
+            constant.position.clone(),
+            format!("'{}' is declared as a constant here", constant.name),
+        )
+        .with_help(format!(
+            "declare '{}' without 'const' to asign to it",
+            constant.name
+        ))
+    }
+
+    fn asign_variable(&self, variable_name: &Token, value: &Expression) -> Res<()> {
+        match self.get_variable(&variable_name.token.to_string()) {
+            Some(var) if var.constant.is_some() => {
+                Err(Self::constant_asignment(var, variable_name.position.clone()).into())
             }
+            Some(var) => Self::expect_type(&var.var_type, value),
             None => ErrorHandler::compiler_err(
                 ErrorTypes::UnexpectedSymbol,
                 format!(
                     "expected a variable name, but found no '{}' in this scope",
-                    variable_name.token.to_string()
+                    variable_name.token
                 ),
-                variable_name.position,
+                variable_name.position.clone(),
             ),
         }
     }
 
-    pub fn parse_block(&mut self, tokens: Vec<Token>) -> Res<String> {
-        let mut is_in_block = 0;
+    /// Reports every '}' that closes no block and leaves it out, so the
+    /// statements around it can still be parsed.
+    fn drop_unopened_blocks(&self, tokens: Vec<Token>) -> Vec<Token> {
+        let mut depth = 0;
+        tokens
+            .into_iter()
+            .filter(|token| match token.token {
+                TokenTypes::Control(ControlVariants::OpenCurly) => {
+                    depth += 1;
+                    true
+                }
+                TokenTypes::Control(ControlVariants::CloseCurly) if depth == 0 => {
+                    self.diagnostics.report(CompilerError::new(
+                        ErrorTypes::UnexpectedSymbol,
+                        String::from("no opened block to close"),
+                        token.position.clone(),
+                    ));
+                    false
+                }
+                TokenTypes::Control(ControlVariants::CloseCurly) => {
+                    depth -= 1;
+                    true
+                }
+                _ => true,
+            })
+            .collect()
+    }
+
+    /// Parses the statements of a block. A statement that fails is reported
+    /// and parsing resumes after its ';', so the errors of all statements
+    /// surface in one run.
+    pub fn parse_block(&mut self, tokens: Vec<Token>) -> Res<Vec<Statement>> {
+        let tokens = self.drop_unopened_blocks(tokens);
+        // The depths stay at 0 on a stray ')' or '}', which the statement it
+        // is in reports, so the statements after it are still split.
+        let mut is_in_block: usize = 0;
+        let mut is_in_paranthesis: usize = 0;
         let statements: Vec<&[Token]> = tokens
             .split(|token| match token.token {
+                TokenTypes::Control(ControlVariants::OpenParanthesis) => {
+                    is_in_paranthesis += 1;
+                    false
+                }
+                TokenTypes::Control(ControlVariants::CloseParanthesis) => {
+                    is_in_paranthesis = is_in_paranthesis.saturating_sub(1);
+                    false
+                }
                 TokenTypes::Control(ControlVariants::OpenCurly) => {
                     is_in_block += 1;
                     false
                 }
                 TokenTypes::Control(ControlVariants::CloseCurly) => {
-                    is_in_block -= 1;
-                    if is_in_block < 0 {
-                        panic!(
-                            "{}",
-                            ErrorHandler::compiler_err::<bool>(
-                                ErrorTypes::UnexpectedSymbol,
-                                String::from("no opened block to close"),
-                                token.position.clone()
-                            )
-                            .unwrap_err()
-                        )
-                    }
+                    is_in_block = is_in_block.saturating_sub(1);
                     false
                 }
-                TokenTypes::Control(ControlVariants::Semicolon) => is_in_block == 0,
+                TokenTypes::Control(ControlVariants::Semicolon) => {
+                    is_in_block == 0 && is_in_paranthesis == 0
+                }
                 _ => false,
             })
             .collect();
-        if statements
-            .last()
-            .and_then(|stat| if stat.len() > 0 { Some(true) } else { None })
-            .is_some()
-        {
-            return ErrorHandler::compiler_err(
+        let (last, statements) = statements.split_last().unwrap();
+        let mut block = vec![];
+        for stat in statements {
+            if !stat.is_empty() {
+                block.extend(self.recover_statement(stat)?);
+            }
+        }
+        // A missing ';' is only worth reporting if the statement is fine otherwise.
+        if !last.is_empty() && self.recover_statement(last)?.is_some() {
+            self.diagnostics.report(CompilerError::new(
                 ErrorTypes::UnclosedStatement,
                 String::from("expected ';', but found end of block"),
-                statements.last().unwrap().first().unwrap().position.clone(),
-            );
+                last[0].position.clone(),
+            ));
         }
-        for stat in statements {
-            if stat.len() > 0 {
-                self.parse_statement(stat)?;
-            }
+        for function in self
+            .functions
+            .values()
+            .filter(|function| !function.is_defined)
+        {
+            self.diagnostics.report(CompilerError::new(
+                ErrorTypes::InvalidStatement,
+                format!("function '{}' is declared but never defined", function.name),
+                function.position.clone(),
+            ));
+        }
+        Ok(block)
+    }
+
+    /// Parses a statement, reporting its error unless the statement holds a
+    /// token the tokenizer already reported, which makes the error a follow-up.
+    fn recover_statement(&mut self, tokens: &[Token]) -> Res<Option<Statement>> {
+        match self.parse_statement(tokens) {
+            Err(_) if tokens.iter().any(Token::is_invalid) => Ok(None),
+            result => self.diagnostics.recover(result),
         }
-        println!("Scope: {:?}", self.variables);
-        Ok(String::from(""))
     }
 
-    fn parse_statement(&mut self, tokens: &[Token]) -> Res<String> {
+    fn parse_statement(&mut self, tokens: &[Token]) -> Res<Statement> {
         match &tokens.first().unwrap().token {
             TokenTypes::Control(ControlVariants::OpenCurly) => {
-                if match tokens.last().unwrap().token {
-                    TokenTypes::Control(ControlVariants::CloseCurly) => true,
-                    _ => false,
-                } {
-                    Scope::new().parse_block(
-                        tokens
-                            .get(1..tokens.len() - 1)
-                            .or_else(|| Some(&[]))
-                            .unwrap()
-                            .to_vec(),
-                    )
+                if matches!(
+                    tokens.last().unwrap().token,
+                    TokenTypes::Control(ControlVariants::CloseCurly)
+                ) {
+                    let position = tokens.first().unwrap().position.clone();
+                    self.child()
+                        .parse_block(tokens.get(1..tokens.len() - 1).unwrap_or(&[]).to_vec())
+                        .map(|statements| {
+                            Statement::new(
+                                StatementKind::Block(Block::new(statements, position.clone())),
+                                position,
+                            )
+                        })
                 } else {
-                    println!("{:?}", tokens);
                     ErrorHandler::compiler_err(
                         ErrorTypes::UnclosedBlock,
                         String::from("expected '}', but encountered ';'"),
@@ -199,185 +427,2525 @@ impl Scope {
             }
             TokenTypes::Word(WordVariants::Str)
             | TokenTypes::Word(WordVariants::Bool)
-            | TokenTypes::Word(WordVariants::Num) => self.parse_variable_instantiation(tokens),
+            | TokenTypes::Word(WordVariants::Int)
+            | TokenTypes::Word(WordVariants::Float) => {
+                self.parse_variable_instantiation(tokens, false)
+            }
+            TokenTypes::Word(WordVariants::Const) => match tokens.get(1) {
+                Some(_) if self.parse_type(&tokens[1..]).is_some() => {
+                    self.parse_variable_instantiation(&tokens[1..], true)
+                }
+                Some(tok) => ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    format!("expected a type but found '{}'", tok.token),
+                    tok.position.clone(),
+                ),
+                None => ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    String::from("expected a type but found nothing"),
+                    tokens[0].position.clone(),
+                ),
+            },
+            TokenTypes::Word(WordVariants::If) => self.parse_if(tokens),
+            TokenTypes::Word(WordVariants::While) => self.parse_while(tokens),
+            TokenTypes::Word(WordVariants::For) => self.parse_for(tokens),
+            TokenTypes::Word(WordVariants::Func) => self.parse_function_definition(tokens),
+            TokenTypes::Word(WordVariants::Return) => self.parse_return(tokens),
+            TokenTypes::Word(WordVariants::Def) => self.parse_record_definition(tokens),
+            TokenTypes::Name(_)
+                if matches!(
+                    self.parse_type(tokens),
+                    Some((_, length)) if matches!(
+                        tokens.get(length).map(|tok| &tok.token),
+                        Some(TokenTypes::Name(_))
+                    )
+                ) =>
+            {
+                self.parse_variable_instantiation(tokens, false)
+            }
+            TokenTypes::Word(WordVariants::Break) | TokenTypes::Word(WordVariants::Continue) => {
+                self.parse_loop_control(tokens)
+            }
             TokenTypes::Name(_) => self.parse_name_expression(tokens),
-            x if x.is_literal() => self.parse_expression(tokens).and_then(|(vt, v)| Ok(v)),
+            x if x.is_literal() || matches!(x, TokenTypes::Word(WordVariants::Match)) => {
+                self.parse_expression(tokens).map(|expression| {
+                    let position = expression.position.clone();
+                    Statement::new(StatementKind::Expression(expression), position)
+                })
+            }
             _ => ErrorHandler::compiler_err(
                 ErrorTypes::UnexpectedSymbol,
                 format!(
                     "cannot start statement with '{}'",
-                    tokens.first().unwrap().token.to_string()
+                    tokens.first().unwrap().token
                 ),
                 tokens.first().unwrap().position.clone(),
             ),
         }
     }
 
-    fn parse_variable_instantiation(&mut self, tokens: &[Token]) -> Res<String> {
-        let mut tokens = tokens.to_vec();
-        let variable_type = tokens.remove(0);
-        let (variable_name, name_position) = match tokens
-            .first()
-            .and_then(|tok| Some((tok.token.clone(), tok.position.clone())))
-        {
-            Some((TokenTypes::Name(n), pos)) => (n.to_string(), pos),
-            Some((tok, pos)) => {
+    fn parse_if(&mut self, tokens: &[Token]) -> Res<Statement> {
+        let mut branches = vec![];
+        let mut otherwise = None;
+        let mut current = 0;
+        loop {
+            let keyword = &tokens[current];
+            current += 1;
+            let condition = self.parse_condition(tokens, &mut current, keyword)?;
+            let body = self.parse_body(tokens, &mut current, keyword, false)?;
+            branches.push((condition, body));
+            match tokens.get(current) {
+                Some(Token {
+                    token: TokenTypes::Word(WordVariants::Else),
+                    ..
+                }) => current += 1,
+                _ => break,
+            }
+            match tokens.get(current) {
+                Some(Token {
+                    token: TokenTypes::Word(WordVariants::If),
+                    ..
+                }) => continue,
+                _ => {
+                    let keyword = &tokens[current - 1];
+                    otherwise = Some(self.parse_body(tokens, &mut current, keyword, false)?);
+                    break;
+                }
+            }
+        }
+        if let Some(tok) = tokens.get(current) {
+            return ErrorHandler::compiler_err(
+                ErrorTypes::UnclosedStatement,
+                format!("expected ';' but found '{}'", tok.token),
+                tok.position.clone(),
+            );
+        }
+        Ok(Statement::new(
+            StatementKind::If {
+                branches,
+                otherwise,
+            },
+            tokens[0].position.clone(),
+        ))
+    }
+
+    fn parse_while(&mut self, tokens: &[Token]) -> Res<Statement> {
+        let keyword = &tokens[0];
+        let mut current = 1;
+        let condition = self.parse_condition(tokens, &mut current, keyword)?;
+        let body = self.parse_body(tokens, &mut current, keyword, true)?;
+        if let Some(tok) = tokens.get(current) {
+            return ErrorHandler::compiler_err(
+                ErrorTypes::UnclosedStatement,
+                format!("expected ';' but found '{}'", tok.token),
+                tok.position.clone(),
+            );
+        }
+        Ok(Statement::new(
+            StatementKind::While { condition, body },
+            keyword.position.clone(),
+        ))
+    }
+
+    fn parse_for(&mut self, tokens: &[Token]) -> Res<Statement> {
+        let keyword = &tokens[0];
+        let open = match tokens.get(1) {
+            Some(Token {
+                token: TokenTypes::Control(ControlVariants::OpenParanthesis),
+                ..
+            }) => 1,
+            Some(tok) => {
                 return ErrorHandler::compiler_err(
                     ErrorTypes::InvalidStatement,
-                    format!("expected a name but found '{}'", tok.to_string()),
-                    pos,
+                    format!("expected '(' but found '{}'", tok.token),
+                    tok.position.clone(),
                 )
             }
             None => {
                 return ErrorHandler::compiler_err(
                     ErrorTypes::InvalidStatement,
-                    String::from("expected a name but found nothing"),
-                    variable_type.position.clone(),
+                    String::from("expected a loop header after 'for' but found nothing"),
+                    keyword.position.clone(),
                 )
             }
         };
-        match tokens
-            .get(1)
-            .and_then(|tok| Some((tok.token.clone(), tok.position.clone())))
-        {
-            Some((TokenTypes::Control(ControlVariants::Asign), _)) => (),
-            Some((tok, pos)) => {
+        let close = Self::find_closing(tokens, open)?;
+        let header = Self::split_top_level(&tokens[open + 1..close], ControlVariants::Semicolon);
+        if header.len() == 1 {
+            return self.parse_for_each(tokens, open, close);
+        }
+        let [init, condition, update] = header[..] else {
+            return ErrorHandler::compiler_err(
+                ErrorTypes::InvalidStatement,
+                format!(
+                    "expected 'init; condition; update' in the loop header but found {} parts",
+                    header.len()
+                ),
+                tokens[open].position.clone(),
+            );
+        };
+        let mut scope = self.child();
+        let init = match init {
+            [] => None,
+            toks => Some(Box::new(scope.parse_statement(toks)?)),
+        };
+        let condition = match condition {
+            [] => None,
+            toks => {
+                let condition = scope.parse_expression(toks)?;
+                if condition.value_type != Type::Bool {
+                    return ErrorHandler::compiler_err(
+                        ErrorTypes::MismatchedTypes,
+                        format!(
+                            "expected bool-type condition but found {}-type",
+                            condition.value_type
+                        ),
+                        condition.position.clone(),
+                    );
+                }
+                Some(condition)
+            }
+        };
+        let update = match update {
+            [] => None,
+            toks => Some(Box::new(scope.parse_statement(toks)?)),
+        };
+        let mut current = close + 1;
+        let body = scope.parse_body(tokens, &mut current, keyword, true)?;
+        if let Some(tok) = tokens.get(current) {
+            return ErrorHandler::compiler_err(
+                ErrorTypes::UnclosedStatement,
+                format!("expected ';' but found '{}'", tok.token),
+                tok.position.clone(),
+            );
+        }
+        Ok(Statement::new(
+            StatementKind::For {
+                init,
+                condition,
+                update,
+                body,
+            },
+            keyword.position.clone(),
+        ))
+    }
+
+    /// Parses `for (type name : array) { ... }` where the header spans from
+    /// `open` to `close`.
+    fn parse_for_each(&mut self, tokens: &[Token], open: usize, close: usize) -> Res<Statement> {
+        let keyword = &tokens[0];
+        let header = &tokens[open + 1..close];
+        // Only the first ':' separates the variable, the array may contain more.
+        let declaration = Self::split_top_level(header, ControlVariants::Colon)[0];
+        let array = header.get(declaration.len() + 1..).unwrap_or(&[]);
+        let (var_type, name, position, array) = match declaration {
+            [type_tokens @ .., Token {
+                token: TokenTypes::Name(name),
+                position,
+            }] if self.is_type(type_tokens) && !array.is_empty() => (
+                self.parse_type(type_tokens).unwrap().0,
+                name.to_string(),
+                position.clone(),
+                array,
+            ),
+            _ => return ErrorHandler::compiler_err(
+                ErrorTypes::InvalidStatement,
+                String::from(
+                    "expected 'init; condition; update' or 'type name : array' in the loop header",
+                ),
+                tokens[open].position.clone(),
+            ),
+        };
+        let array = self.parse_expression(array)?;
+        Self::expect_type(&Type::Array(Box::new(var_type.clone())), &array)?;
+        let mut scope = self.child();
+        scope.create_variable(var_type.clone(), name.clone(), position, None)?;
+        let mut current = close + 1;
+        let body = scope.parse_body(tokens, &mut current, keyword, true)?;
+        if let Some(tok) = tokens.get(current) {
+            return ErrorHandler::compiler_err(
+                ErrorTypes::UnclosedStatement,
+                format!("expected ';' but found '{}'", tok.token),
+                tok.position.clone(),
+            );
+        }
+        Ok(Statement::new(
+            StatementKind::ForEach {
+                name,
+                var_type,
+                array,
+                body,
+            },
+            keyword.position.clone(),
+        ))
+    }
+
+    /// Splits `tokens` at every `delimiter` that is not nested in brackets.
+    fn split_top_level(tokens: &[Token], delimiter: ControlVariants) -> Vec<&[Token]> {
+        let mut depth = 0;
+        tokens
+            .split(|token| match &token.token {
+                TokenTypes::Control(
+                    ControlVariants::OpenParanthesis
+                    | ControlVariants::OpenCurly
+                    | ControlVariants::OpenSquare,
+                ) => {
+                    depth += 1;
+                    false
+                }
+                TokenTypes::Control(
+                    ControlVariants::CloseParanthesis
+                    | ControlVariants::CloseCurly
+                    | ControlVariants::CloseSquare,
+                ) => {
+                    depth -= 1;
+                    false
+                }
+                TokenTypes::Control(c) => depth == 0 && *c == delimiter,
+                _ => false,
+            })
+            .collect()
+    }
+
+    fn parse_function_definition(&mut self, tokens: &[Token]) -> Res<Statement> {
+        let keyword = &tokens[0];
+        let name = match tokens.get(1) {
+            Some(Token {
+                token: TokenTypes::Name(name),
+                position,
+            }) => Token {
+                token: TokenTypes::Name(name.clone()),
+                position: position.clone(),
+            },
+            Some(tok) => {
                 return ErrorHandler::compiler_err(
                     ErrorTypes::InvalidStatement,
-                    format!("expected '=' but found '{}'", tok.to_string()),
-                    pos,
+                    format!("expected a function name but found '{}'", tok.token),
+                    tok.position.clone(),
                 )
             }
             None => {
                 return ErrorHandler::compiler_err(
                     ErrorTypes::InvalidStatement,
-                    String::from("expected '=' but found nothing"),
-                    name_position,
+                    String::from("expected a function name but found nothing"),
+                    keyword.position.clone(),
                 )
             }
         };
-        self.create_variable(
-            variable_type.token.to_string(),
-            variable_name,
-            name_position,
+        let mut current = 2;
+        let mut parameter = vec![];
+        // The parameter list may be written with or without `args`.
+        if let Some(Token {
+            token: TokenTypes::Word(WordVariants::Args),
+            ..
+        }) = tokens.get(current)
+        {
+            current += 1;
+            match tokens.get(current) {
+                Some(Token {
+                    token: TokenTypes::Control(ControlVariants::OpenParanthesis),
+                    ..
+                }) => (),
+                Some(tok) => {
+                    return ErrorHandler::compiler_err(
+                        ErrorTypes::InvalidStatement,
+                        format!("expected '(' but found '{}'", tok.token),
+                        tok.position.clone(),
+                    )
+                }
+                None => {
+                    return ErrorHandler::compiler_err(
+                        ErrorTypes::InvalidStatement,
+                        String::from("expected '(' but found nothing"),
+                        tokens[current - 1].position.clone(),
+                    )
+                }
+            }
+        }
+        if let Some(Token {
+            token: TokenTypes::Control(ControlVariants::OpenParanthesis),
+            ..
+        }) = tokens.get(current)
+        {
+            let close = Self::find_closing(tokens, current)?;
+            parameter = self.parse_parameters(&tokens[current + 1..close], &tokens[current])?;
+            current = close + 1;
+        }
+        let mut return_type = Type::Void;
+        if let Some(Token {
+            token: TokenTypes::Control(ControlVariants::Colon),
+            position,
+        }) = tokens.get(current)
+        {
+            let length;
+            (return_type, length) = match self.parse_type(&tokens[current + 1..]) {
+                Some(return_type) => return_type,
+                None => {
+                    return ErrorHandler::compiler_err(
+                        ErrorTypes::InvalidStatement,
+                        String::from("expected a return type after ':'"),
+                        position.clone(),
+                    )
+                }
+            };
+            current += 1 + length;
+        }
+
+        let function_name = name.token.to_string();
+        let is_declaration = tokens.get(current).is_none();
+        match self.functions.get(&function_name) {
+            Some(function) if !function.is_defined && !is_declaration => {
+                let parameter_types = parameter.iter().map(|variable| &variable.var_type);
+                if function.return_type != return_type
+                    || !function
+                        .parameter
+                        .iter()
+                        .map(|variable| &variable.var_type)
+                        .eq(parameter_types)
+                {
+                    // The body is still worth checking against the definition.
+                    self.diagnostics.report(
+                        CompilerError::new(
+                            ErrorTypes::MismatchedTypes,
+                            format!(
+                                "definition of '{}' does not match its declaration",
+                                function_name
+                            ),
+                            name.position.clone(),
+                        )
+                        .with_label(
+                            function.position.clone(),
+                            format!("'{}' declared here", function_name),
+                        ),
+                    );
+                }
+            }
+            Some(function) => {
+                let error = CompilerError::new(
+                    ErrorTypes::DuplicateDefinition,
+                    format!("'{}' is already defined in this scope", function_name),
+                    name.position,
+                );
+                return Err(if function.is_inbuild {
+                    error.with_help(format!("'{}' is an inbuild function", function_name))
+                } else {
+                    error.with_label(
+                        function.position.clone(),
+                        format!("previous definition of '{}' here", function_name),
+                    )
+                }
+                .into());
+            }
+            None => (),
+        }
+        let parameter_declarations = parameter
+            .iter()
+            .map(|variable: &Variable| {
+                (
+                    variable.name.clone(),
+                    variable.var_type.clone(),
+                    variable.position.clone(),
+                )
+            })
+            .collect::<Vec<(String, Type, Position)>>();
+        self.functions.insert(
+            function_name.clone(),
+            Function {
+                is_defined: !is_declaration,
+                ..Function::new(
+                    function_name.clone(),
+                    name.position.clone(),
+                    parameter,
+                    return_type.clone(),
+                )
+            },
         );
-        self.parse_variable_asignment(&tokens)
-    }
+        if is_declaration {
+            return Ok(Statement::new(
+                StatementKind::FunctionDeclaration {
+                    name: function_name,
+                },
+                keyword.position.clone(),
+            ));
+        }
 
-    fn parse_variable_asignment(&mut self, tokens: &[Token]) -> Res<String> {
-        let tokens = tokens.to_vec();
-        let variable_name = &tokens[0];
-        let (value_type, value) = match tokens.get(2..) {
-            Some([]) | None => {
+        let mut scope = self.child();
+        scope.return_type = Some(return_type.clone());
+        for (parameter_name, parameter_type, position) in &parameter_declarations {
+            scope.create_variable(
+                parameter_type.clone(),
+                parameter_name.clone(),
+                position.clone(),
+                None,
+            )?;
+        }
+        let reported = self.diagnostics.len();
+        let body = match &tokens[current] {
+            Token {
+                token: TokenTypes::Control(ControlVariants::OpenCurly),
+                ..
+            } => {
+                let close = Self::find_closing(tokens, current)?;
+                let statements = scope.parse_block(tokens[current + 1..close].to_vec())?;
+                let body = Block::new(statements, tokens[current].position.clone());
+                current = close + 1;
+                body
+            }
+            tok => {
                 return ErrorHandler::compiler_err(
                     ErrorTypes::InvalidStatement,
-                    String::from("expected an expression, but found nothing"),
-                    tokens[1].position.clone(),
+                    format!("expected '{{' but found '{}'", tok.token),
+                    tok.position.clone(),
                 )
             }
-            Some(toks) => self.parse_expression(toks),
-        }?;
-        self.asign_variable(variable_name.clone(), value_type, value)?;
-        Ok(String::from(""))
+        };
+        if let Some(tok) = tokens.get(current) {
+            return ErrorHandler::compiler_err(
+                ErrorTypes::UnclosedStatement,
+                format!("expected ';' but found '{}'", tok.token),
+                tok.position.clone(),
+            );
+        }
+        // a body with errors may have lost the statements that return
+        if return_type != Type::Void
+            && self.diagnostics.len() == reported
+            && !Self::always_returns(&body.statements)
+        {
+            return ErrorHandler::compiler_err(
+                ErrorTypes::MismatchedTypes,
+                format!(
+                    "function '{}' does not return a {}-type on every path",
+                    function_name, return_type
+                ),
+                name.position,
+            );
+        }
+        Ok(Statement::new(
+            StatementKind::Function {
+                name: function_name,
+                parameter: parameter_declarations
+                    .into_iter()
+                    .map(|(name, var_type, _)| (name, var_type))
+                    .collect(),
+                return_type,
+                body,
+            },
+            keyword.position.clone(),
+        ))
     }
 
-    fn parse_name_expression(&mut self, tokens: &[Token]) -> Res<String> {
-        let variable_name = &tokens[0];
-        match tokens
-            .get(1)
-            .and_then(|tok| Some((tok.token.clone(), tok.position.clone())))
-        {
-            Some((TokenTypes::Control(ControlVariants::Asign), _)) => {
-                self.parse_variable_asignment(tokens)
-            }
-            Some((TokenTypes::Control(ControlVariants::OpenParanthesis), _)) => {
-                self.parse_function_call(tokens)
+    fn parse_record_definition(&mut self, tokens: &[Token]) -> Res<Statement> {
+        let keyword = &tokens[0];
+        let (name, position) = match tokens.get(1) {
+            Some(Token {
+                token: TokenTypes::Name(name),
+                position,
+            }) => (name.to_string(), position.clone()),
+            Some(tok) => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    format!("expected a type name but found '{}'", tok.token),
+                    tok.position.clone(),
+                )
             }
-            Some((tok, pos)) => ErrorHandler::compiler_err(
-                ErrorTypes::InvalidStatement,
-                format!("expected '=' but found '{}'", tok.to_string()),
-                pos,
-            ),
-            None => ErrorHandler::compiler_err(
-                ErrorTypes::InvalidStatement,
-                String::from("expected '=' but found nothing"),
-                variable_name.position.clone(),
-            ),
+            None => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    String::from("expected a type name but found nothing"),
+                    keyword.position.clone(),
+                )
+            }
+        };
+        if let Some(record) = self.records.get(&name) {
+            return Err(CompilerError::new(
+                ErrorTypes::DuplicateDefinition,
+                format!("'{}' is already defined in this scope", name),
+                position,
+            )
+            .with_label(
+                record.position.clone(),
+                format!("previous definition of '{}' here", name),
+            )
+            .into());
+        }
+        match tokens.get(2) {
+            Some(Token {
+                token: TokenTypes::Control(ControlVariants::OpenCurly),
+                ..
+            }) => (),
+            Some(tok) => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    format!("expected '{{' but found '{}'", tok.token),
+                    tok.position.clone(),
+                )
+            }
+            None => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    String::from("expected the fields of the type but found nothing"),
+                    position,
+                )
+            }
+        }
+        let close = Self::find_closing(tokens, 2)?;
+        if let Some(tok) = tokens.get(close + 1) {
+            return ErrorHandler::compiler_err(
+                ErrorTypes::UnclosedStatement,
+                format!("expected ';' but found '{}'", tok.token),
+                tok.position.clone(),
+            );
+        }
+        let mut field_tokens = &tokens[3..close];
+        if let [rest @ .., Token {
+            token: TokenTypes::Control(ControlVariants::Comma),
+            ..
+        }] = field_tokens
+        {
+            field_tokens = rest;
         }
+        let fields = self
+            .parse_parameters(field_tokens, &tokens[2])?
+            .into_iter()
+            .map(|field| (field.name, field.var_type))
+            .collect::<Vec<(String, Type)>>();
+        self.records.insert(
+            name.clone(),
+            Record::new(name.clone(), position, fields.clone()),
+        );
+        Ok(Statement::new(
+            StatementKind::Record { name, fields },
+            keyword.position.clone(),
+        ))
     }
 
-    fn parse_function_call(&self, tokens: &[Token]) -> Res<String> {
-        let variable_name = &tokens[0];
-        let input = match tokens.get(2..tokens.len() - 1) {
-            Some(toks) => self.parse_expression(toks),
-            None => ErrorHandler::compiler_err(
+    fn parse_parameters(&self, tokens: &[Token], open: &Token) -> Res<Vec<Variable>> {
+        if tokens.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut parameter: Vec<Variable> = vec![];
+        for declaration in Self::split_top_level(tokens, ControlVariants::Comma) {
+            match declaration {
+                [var_type @ .., Token {
+                    token: TokenTypes::Name(name),
+                    position,
+                }] if self.is_type(var_type) => {
+                    if let Some(previous) = parameter.iter().find(|p| p.name == name.to_string()) {
+                        return Err(CompilerError::new(
+                            ErrorTypes::DuplicateDefinition,
+                            format!("'{}' is already defined in this scope", name.to_string()),
+                            position.clone(),
+                        )
+                        .with_label(
+                            previous.position.clone(),
+                            format!("previous definition of '{}' here", name.to_string()),
+                        )
+                        .into());
+                    }
+                    parameter.push(Variable::new(
+                        self.parse_type(var_type).unwrap().0,
+                        name.to_string(),
+                        position.clone(),
+                    ))
+                }
+                [] => {
+                    return ErrorHandler::compiler_err(
+                        ErrorTypes::InvalidStatement,
+                        String::from("expected a parameter but found nothing"),
+                        open.position.clone(),
+                    )
+                }
+                toks => {
+                    return ErrorHandler::compiler_err(
+                        ErrorTypes::InvalidStatement,
+                        String::from("expected a parameter of the form 'type name'"),
+                        toks[0].position.clone(),
+                    )
+                }
+            }
+        }
+        Ok(parameter)
+    }
+
+    /// Whether every path through `statements` ends in a `return`.
+    fn always_returns(statements: &[Statement]) -> bool {
+        statements.iter().any(|statement| match &statement.kind {
+            StatementKind::Return(_) => true,
+            StatementKind::Block(block) => Self::always_returns(&block.statements),
+            StatementKind::If {
+                branches,
+                otherwise: Some(otherwise),
+            } => {
+                branches
+                    .iter()
+                    .all(|(_, body)| Self::always_returns(&body.statements))
+                    && Self::always_returns(&otherwise.statements)
+            }
+            _ => false,
+        })
+    }
+
+    fn parse_return(&mut self, tokens: &[Token]) -> Res<Statement> {
+        let keyword = &tokens[0];
+        let return_type = match self.function_return_type() {
+            Some(return_type) => return_type.clone(),
+            None => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    String::from("'return' outside of a function"),
+                    keyword.position.clone(),
+                )
+            }
+        };
+        let value = match &tokens[1..] {
+            [] => None,
+            toks => Some(self.parse_expression(toks)?),
+        };
+        let value_type = value
+            .as_ref()
+            .map_or(Type::Void, |value| value.value_type.clone());
+        if !return_type.accepts(&value_type) {
+            return ErrorHandler::compiler_err(
+                ErrorTypes::MismatchedTypes,
+                format!(
+                    "expected {}-type but found a {}-type",
+                    return_type, value_type
+                ),
+                value.map_or(keyword.position.clone(), |value| value.position),
+            );
+        }
+        Ok(Statement::new(
+            StatementKind::Return(value),
+            keyword.position.clone(),
+        ))
+    }
+
+    fn parse_loop_control(&mut self, tokens: &[Token]) -> Res<Statement> {
+        let keyword = &tokens[0];
+        if !self.is_in_loop() {
+            return ErrorHandler::compiler_err(
                 ErrorTypes::InvalidStatement,
-                String::from("expected function parameters, but found nothing"),
-                tokens[1].position.clone(),
-            ),
-        }?;
-        Ok(format!(""))
+                format!("'{}' outside of a loop", keyword.token),
+                keyword.position.clone(),
+            );
+        }
+        if let Some(tok) = tokens.get(1) {
+            return ErrorHandler::compiler_err(
+                ErrorTypes::UnclosedStatement,
+                format!("expected ';' but found '{}'", tok.token),
+                tok.position.clone(),
+            );
+        }
+        let kind = match keyword.token {
+            TokenTypes::Word(WordVariants::Break) => StatementKind::Break,
+            _ => StatementKind::Continue,
+        };
+        Ok(Statement::new(kind, keyword.position.clone()))
     }
 
-    fn parse_expression(&self, tokens: &[Token]) -> Res<(String, String)> {
-        println!("Parsing Expression: {:?}", tokens);
-        let (value_type, value) = match tokens.len() {
-            //TODO:
-            //MAYBE DO A FUCKING RUNTIME INSTEAD OF A COMPILER
-            1 => match tokens[0].token.clone() {
-                x if x.is_literal() => Ok((x.token_type().to_owned(), x.to_string())),
-                _ => ErrorHandler::compiler_err(
+    /// Parses the parenthesized `bool` condition following `keyword`.
+    fn parse_condition(
+        &self,
+        tokens: &[Token],
+        current: &mut usize,
+        keyword: &Token,
+    ) -> Res<Expression> {
+        match tokens.get(*current) {
+            Some(Token {
+                token: TokenTypes::Control(ControlVariants::OpenParanthesis),
+                ..
+            }) => (),
+            Some(tok) => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    format!("expected '(' but found '{}'", tok.token),
+                    tok.position.clone(),
+                )
+            }
+            None => {
+                return ErrorHandler::compiler_err(
                     ErrorTypes::InvalidStatement,
                     format!(
-                        "expected literal but found '{}'",
-                        tokens[0].token.to_string()
+                        "expected a condition after '{}' but found nothing",
+                        keyword.token
                     ),
-                    tokens[0].position.clone(),
+                    keyword.position.clone(),
+                )
+            }
+        }
+        let close = Self::find_closing(tokens, *current)?;
+        let condition = match &tokens[*current + 1..close] {
+            [] => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    String::from("expected a condition but found nothing"),
+                    tokens[*current].position.clone(),
+                )
+            }
+            toks => self.parse_expression(toks)?,
+        };
+        *current = close + 1;
+        if condition.value_type != Type::Bool {
+            return ErrorHandler::compiler_err(
+                ErrorTypes::MismatchedTypes,
+                format!(
+                    "expected bool-type condition but found {}-type",
+                    condition.value_type
                 ),
+                condition.position.clone(),
+            );
+        }
+        Ok(condition)
+    }
+
+    /// Parses the `{ ... }` body following `keyword` in its own nested scope.
+    fn parse_body(
+        &self,
+        tokens: &[Token],
+        current: &mut usize,
+        keyword: &Token,
+        is_loop: bool,
+    ) -> Res<Block> {
+        match tokens.get(*current) {
+            Some(Token {
+                token: TokenTypes::Control(ControlVariants::OpenCurly),
+                ..
+            }) => (),
+            Some(tok) => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    format!("expected '{{' but found '{}'", tok.token),
+                    tok.position.clone(),
+                )
+            }
+            None => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    format!(
+                        "expected a block after '{}' but found nothing",
+                        keyword.token
+                    ),
+                    keyword.position.clone(),
+                )
+            }
+        }
+        let close = Self::find_closing(tokens, *current)?;
+        let position = tokens[*current].position.clone();
+        let mut scope = self.child();
+        scope.is_loop = is_loop;
+        let statements = scope.parse_block(tokens[*current + 1..close].to_vec())?;
+        *current = close + 1;
+        Ok(Block::new(statements, position))
+    }
+
+    fn parse_variable_instantiation(&mut self, tokens: &[Token], is_const: bool) -> Res<Statement> {
+        let (var_type, length) = self.parse_type(tokens).unwrap();
+        let type_position = tokens[0].position.clone();
+        let tokens = &tokens[length..];
+        let (variable_name, name_position) = match tokens
+            .first()
+            .map(|tok| (tok.token.clone(), tok.position.clone()))
+        {
+            Some((TokenTypes::Name(n), pos)) => (n.to_string(), pos),
+            Some((tok, pos)) => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    format!("expected a name but found '{}'", tok),
+                    pos,
+                )
+            }
+            None => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    String::from("expected a name but found nothing"),
+                    type_position.clone(),
+                )
+            }
+        };
+        match tokens
+            .get(1)
+            .map(|tok| (tok.token.clone(), tok.position.clone()))
+        {
+            Some((TokenTypes::Control(ControlVariants::Asign), _)) => (),
+            Some((tok, pos)) => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    format!("expected '=' but found '{}'", tok),
+                    pos,
+                )
+            }
+            None => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    String::from("expected '=' but found nothing"),
+                    name_position,
+                )
+            }
+        };
+        let mut value = match self
+            .parse_asigned_value(tokens)
+            .and_then(|value| Self::expect_type(&var_type, &value).map(|_| value))
+        {
+            Ok(value) => value,
+            Err(error) => {
+                // Declared anyway, so later uses are not reported as unknown.
+                let _ = self.create_variable(var_type, variable_name, name_position, None);
+                return Err(error);
+            }
+        };
+        let constant = if is_const {
+            let folded = Self::fold_constant(&value).and_then(|constant| {
+                constant.ok_or_else(|| {
+                    CompilerError::new(
+                        ErrorTypes::InvalidStatement,
+                        format!(
+                            "expected a compile-time constant for '{}' but found '{}'",
+                            variable_name, value
+                        ),
+                        value.position.clone(),
+                    )
+                    .into()
+                })
+            });
+            match folded {
+                Ok(literal) => {
+                    value = Expression::new(
+                        ExpressionKind::Literal(literal.clone()),
+                        var_type.clone(),
+                        value.position,
+                    );
+                    Some(literal)
+                }
+                Err(error) => {
+                    let _ = self.create_variable(var_type, variable_name, name_position, None);
+                    return Err(error);
+                }
+            }
+        } else {
+            None
+        };
+        self.create_variable(
+            var_type.clone(),
+            variable_name.clone(),
+            name_position,
+            constant,
+        )?;
+        Ok(Statement::new(
+            StatementKind::Declaration {
+                name: variable_name,
+                var_type,
+                value,
+                is_const,
             },
-            x if x & 1 == 1 => {
-                let (left_type, left_value) = self.parse_expression(tokens.get(0..1).unwrap())?;
-                let operator = &tokens[1];
-                let right = tokens.get(2..).unwrap();
-                let (right_type, right_value) = self.parse_expression(right)?;
-                match operator.token.clone() {
-                    TokenTypes::Control(ControlVariants::Comma) => Ok((
-                        format!("{}, {}", left_type, right_type),
-                        format!("{}, {}", left_value, right_value),
-                    )),
-                    op => {
-                        if right_type != left_type {
-                            ErrorHandler::compiler_err(
-                                ErrorTypes::MismatchedTypes,
-                                format!(
-                                    "expected {}-type but found {}-type",
-                                    left_type, right_type
-                                ),
-                                right.last().unwrap().position.clone(),
-                            )
-                        } else {
-                            Ok((
-                                left_type,
-                                format!("{} {} {}", left_value, op.to_string(), right_value),
-                            ))
+            type_position,
+        ))
+    }
+
+    /// Evaluates expressions made only of literals and constants. Expressions
+    /// that are not constant are `None`, arithmetic that fails is an error.
+    fn fold_constant(expression: &Expression) -> Res<Option<Literal>> {
+        let arithmetic_err = |message: String| {
+            ErrorHandler::compiler_err(
+                ErrorTypes::ArithmeticError,
+                message,
+                expression.position.clone(),
+            )
+        };
+        Ok(Some(match &expression.kind {
+            ExpressionKind::Literal(literal) => literal.clone(),
+            ExpressionKind::Unary { operator, operand } => {
+                let Some(operand) = Self::fold_constant(operand)? else {
+                    return Ok(None);
+                };
+                match (operator, operand) {
+                    (ControlVariants::Minus, Literal::Int(int)) => match int.checked_neg() {
+                        Some(int) => Literal::Int(int),
+                        None => return arithmetic_err(format!("'-{}' overflows an int", int)),
+                    },
+                    (ControlVariants::Minus, Literal::Float(float)) => Literal::Float(-float),
+                    (ControlVariants::Not, Literal::Bool(bool)) => Literal::Bool(!bool),
+                    _ => return Ok(None),
+                }
+            }
+            ExpressionKind::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                let (Some(left), Some(right)) =
+                    (Self::fold_constant(left)?, Self::fold_constant(right)?)
+                else {
+                    return Ok(None);
+                };
+                match (operator, left, right) {
+                    (ControlVariants::Equal, l, r) => Literal::Bool(l == r),
+                    (ControlVariants::NotEqual, l, r) => Literal::Bool(l != r),
+                    (ControlVariants::Plus, Literal::Str(l), Literal::Str(r)) => {
+                        Literal::Str(l + &r)
+                    }
+                    (op, Literal::Int(l), Literal::Int(r)) => {
+                        match comparison(op, l.partial_cmp(&r)) {
+                            Some(bool) => Literal::Bool(bool),
+                            None => match int_arithmetic(op, l, r) {
+                                Ok(int) => Literal::Int(int),
+                                Err(message) => return arithmetic_err(message),
+                            },
+                        }
+                    }
+                    (op, Literal::Float(l), Literal::Float(r)) => {
+                        match comparison(op, l.partial_cmp(&r)) {
+                            Some(bool) => Literal::Bool(bool),
+                            None => match float_arithmetic(op, l, r) {
+                                Some(float) => Literal::Float(float),
+                                None => return Ok(None),
+                            },
                         }
                     }
+                    (ControlVariants::And, Literal::Bool(l), Literal::Bool(r)) => {
+                        Literal::Bool(l && r)
+                    }
+                    (ControlVariants::Or, Literal::Bool(l), Literal::Bool(r)) => {
+                        Literal::Bool(l || r)
+                    }
+                    _ => return Ok(None),
                 }
             }
-            _ => ErrorHandler::compiler_err(
+            ExpressionKind::Conversion(value) => {
+                let Some(value) = Self::fold_constant(value)? else {
+                    return Ok(None);
+                };
+                match (&expression.value_type, value) {
+                    (Type::Int, Literal::Float(float)) => match float_to_int(float) {
+                        Ok(int) => Literal::Int(int),
+                        Err(message) => return arithmetic_err(message),
+                    },
+                    (Type::Float, Literal::Int(int)) => Literal::Float(int as f64),
+                    (_, literal) => literal,
+                }
+            }
+            ExpressionKind::Interpolation(parts) => {
+                let mut str = String::new();
+                for part in parts {
+                    match Self::fold_constant(part)? {
+                        Some(Literal::Str(part)) => str.push_str(&part),
+                        Some(literal) => str.push_str(&literal.to_string()),
+                        None => return Ok(None),
+                    }
+                }
+                Literal::Str(str)
+            }
+            ExpressionKind::Ternary {
+                condition,
+                then,
+                otherwise,
+            } => match Self::fold_constant(condition)? {
+                Some(Literal::Bool(true)) => return Self::fold_constant(then),
+                Some(Literal::Bool(false)) => return Self::fold_constant(otherwise),
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        }))
+    }
+
+    fn parse_variable_asignment(&mut self, tokens: &[Token]) -> Res<Statement> {
+        let variable_name = &tokens[0];
+        let value = self.parse_asigned_value(tokens)?;
+        self.asign_variable(variable_name, &value)?;
+        let var_type = value.value_type.clone();
+        Ok(Statement::new(
+            StatementKind::Asignment {
+                target: Expression::new(
+                    ExpressionKind::Identifier(variable_name.token.to_string()),
+                    var_type,
+                    variable_name.position.clone(),
+                ),
+                value,
+            },
+            variable_name.position.clone(),
+        ))
+    }
+
+    /// Parses `place = value` where the place is a field or an element of a
+    /// variable.
+    fn parse_place_asignment(&mut self, tokens: &[Token], asign: usize) -> Res<Statement> {
+        let target = self.parse_expression(&tokens[..asign])?;
+        let mut root = &target;
+        while let ExpressionKind::Field { record: inner, .. }
+        | ExpressionKind::Index { array: inner, .. } = &root.kind
+        {
+            root = inner;
+        }
+        match &root.kind {
+            ExpressionKind::Identifier(name) if !std::ptr::eq(root, &target) => {
+                let variable = self.get_variable(name).unwrap();
+                if variable.constant.is_some() {
+                    return Err(Self::constant_asignment(variable, root.position.clone()).into());
+                }
+            }
+            _ => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    format!(
+                        "expected a variable, field or element but found '{}'",
+                        target
+                    ),
+                    target.position.clone(),
+                )
+            }
+        }
+        let value = self.parse_asigned_value(&tokens[asign - 1..])?;
+        Self::expect_type(&target.value_type, &value)?;
+        Ok(Statement::new(
+            StatementKind::Asignment { target, value },
+            tokens[0].position.clone(),
+        ))
+    }
+
+    fn parse_asigned_value(&self, tokens: &[Token]) -> Res<Expression> {
+        match tokens.get(2..) {
+            Some([]) | None => ErrorHandler::compiler_err(
                 ErrorTypes::InvalidStatement,
-                format!(
-                    "expected expression but found '{}'",
-                    tokens
-                        .iter()
-                        .fold(String::from(""), |mut str: String, tok: &Token| {
-                            str.push_str(tok.token.to_string().as_str());
-                            str
-                        })
+                String::from("expected an expression, but found nothing"),
+                tokens[1].position.clone(),
+            ),
+            Some(toks) => self.parse_expression(toks),
+        }
+    }
+
+    fn parse_name_expression(&mut self, tokens: &[Token]) -> Res<Statement> {
+        let variable_name = &tokens[0];
+        if let Some(TokenTypes::Control(ControlVariants::Dot | ControlVariants::OpenSquare)) =
+            tokens.get(1).map(|tok| &tok.token)
+        {
+            return match Self::split_top_level(tokens, ControlVariants::Asign).first() {
+                Some(target) if target.len() < tokens.len() => {
+                    self.parse_place_asignment(tokens, target.len())
+                }
+                _ => ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    String::from("expected '=' but found nothing"),
+                    tokens.last().unwrap().position.clone(),
                 ),
-                tokens.last().unwrap().position.clone(),
+            };
+        }
+        match tokens
+            .get(1)
+            .map(|tok| (tok.token.clone(), tok.position.clone()))
+        {
+            Some((TokenTypes::Control(ControlVariants::Asign), _)) => {
+                self.parse_variable_asignment(tokens)
+            }
+            Some((TokenTypes::Control(ControlVariants::OpenParanthesis), _)) => {
+                self.parse_function_call(tokens).map(|call| {
+                    Statement::new(
+                        StatementKind::Expression(call),
+                        variable_name.position.clone(),
+                    )
+                })
+            }
+            Some((tok, pos)) => ErrorHandler::compiler_err(
+                ErrorTypes::InvalidStatement,
+                format!("expected '=' but found '{}'", tok),
+                pos,
+            ),
+            None => ErrorHandler::compiler_err(
+                ErrorTypes::InvalidStatement,
+                String::from("expected '=' but found nothing"),
+                variable_name.position.clone(),
             ),
-        }?;
-        Ok((value_type, value))
+        }
+    }
+
+    fn parse_function_call(&self, tokens: &[Token]) -> Res<Expression> {
+        let function_name = &tokens[0];
+        let function = match self.get_function(&function_name.token.to_string()) {
+            Some(function) => function,
+            None => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::UnexpectedSymbol,
+                    format!(
+                        "expected a function name, but found no '{}' in this scope",
+                        function_name.token
+                    ),
+                    function_name.position.clone(),
+                )
+            }
+        };
+        if !matches!(
+            tokens.last().unwrap().token,
+            TokenTypes::Control(ControlVariants::CloseParanthesis)
+        ) {
+            return ErrorHandler::compiler_err(
+                ErrorTypes::UnclosedStatement,
+                format!("expected ')', but found '{}'", tokens.last().unwrap().token),
+                tokens.last().unwrap().position.clone(),
+            );
+        }
+        let arguments = match tokens.get(2..tokens.len() - 1) {
+            Some(toks) => self.parse_list(toks)?,
+            None => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    String::from("expected function parameters, but found nothing"),
+                    tokens[1].position.clone(),
+                )
+            }
+        };
+        if !function.is_inbuild {
+            if arguments.len() != function.parameter.len() {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::MismatchedTypes,
+                    format!(
+                        "expected {} arguments for '{}' but found {}",
+                        function.parameter.len(),
+                        function_name.token,
+                        arguments.len()
+                    ),
+                    function_name.position.clone(),
+                );
+            }
+            for (argument, parameter) in arguments.iter().zip(&function.parameter) {
+                if !parameter.var_type.accepts(&argument.value_type) {
+                    return ErrorHandler::compiler_err(
+                        ErrorTypes::MismatchedTypes,
+                        format!(
+                            "expected {}-type for parameter '{}' but found a {}-type",
+                            parameter.var_type, parameter.name, argument.value_type
+                        ),
+                        argument.position.clone(),
+                    );
+                }
+            }
+        }
+        if function.is_inbuild && function.name == "len" {
+            match &arguments[..] {
+                [argument] if matches!(argument.value_type, Type::Array(_) | Type::Str) => (),
+                [argument] => {
+                    return ErrorHandler::compiler_err(
+                        ErrorTypes::MismatchedTypes,
+                        format!(
+                            "expected an array or str-type for 'len' but found a {}-type",
+                            argument.value_type
+                        ),
+                        argument.position.clone(),
+                    )
+                }
+                _ => {
+                    return ErrorHandler::compiler_err(
+                        ErrorTypes::MismatchedTypes,
+                        format!(
+                            "expected 1 arguments for 'len' but found {}",
+                            arguments.len()
+                        ),
+                        function_name.position.clone(),
+                    )
+                }
+            }
+        }
+        Ok(Expression::new(
+            ExpressionKind::Call {
+                name: function_name.token.to_string(),
+                arguments,
+            },
+            function.return_type.clone(),
+            function_name.position.clone(),
+        ))
+    }
+
+    /// Parses the comma separated expressions of an argument list or an
+    /// array literal.
+    fn parse_list(&self, tokens: &[Token]) -> Res<Vec<Expression>> {
+        if tokens.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut elements = vec![];
+        let mut start = 0;
+        for element in Self::split_top_level(tokens, ControlVariants::Comma) {
+            start += element.len();
+            // Every element but the last is followed by its comma.
+            let delimiter = tokens.get(start).unwrap_or(tokens.last().unwrap());
+            start += 1;
+            if element.is_empty() {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    format!("expected an expression before '{}'", delimiter.token),
+                    delimiter.position.clone(),
+                );
+            }
+            elements.push(self.parse_expression(element)?);
+        }
+        Ok(elements)
+    }
+
+    fn binary_type(operator: &Token, left: &Expression, right: &Expression) -> Res<Type> {
+        let op = match &operator.token {
+            TokenTypes::Control(op) => op,
+            tok => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::UnexpectedSymbol,
+                    format!("expected an operator but found '{}'", tok),
+                    operator.position.clone(),
+                )
+            }
+        };
+        if !left.value_type.accepts(&right.value_type)
+            && !right.value_type.accepts(&left.value_type)
+        {
+            let error = CompilerError::new(
+                ErrorTypes::MismatchedTypes,
+                format!(
+                    "expected {}-type but found {}-type",
+                    left.value_type, right.value_type
+                ),
+                right.position.clone(),
+            )
+            .with_label(
+                left.position.clone(),
+                format!("the left side is a {}-type", left.value_type),
+            );
+            return Err(match (&left.value_type, &right.value_type) {
+                (Type::Int, Type::Float) | (Type::Float, Type::Int) => {
+                    error.with_help(format!("convert one side with '{}(...)'", left.value_type))
+                }
+                _ => error,
+            }
+            .into());
+        }
+        let result_type = match (op, &left.value_type) {
+            (ControlVariants::Plus, Type::Int | Type::Float | Type::Str) => left.value_type.clone(),
+            (
+                ControlVariants::Minus
+                | ControlVariants::Multiplikation
+                | ControlVariants::Divide
+                | ControlVariants::Power,
+                Type::Int | Type::Float,
+            ) => left.value_type.clone(),
+            (
+                ControlVariants::Less
+                | ControlVariants::LessEqual
+                | ControlVariants::Greater
+                | ControlVariants::GreaterEqual,
+                Type::Int | Type::Float,
+            ) => Type::Bool,
+            (ControlVariants::Equal | ControlVariants::NotEqual, _) => Type::Bool,
+            (ControlVariants::And | ControlVariants::Or, Type::Bool) => Type::Bool,
+            (ControlVariants::BitAnd | ControlVariants::BitOr, Type::Int) => Type::Int,
+            _ => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::MismatchedTypes,
+                    format!(
+                        "operator '{}' cannot be applied to {}-type",
+                        op.to_string(),
+                        left.value_type
+                    ),
+                    operator.position.clone(),
+                )
+            }
+        };
+        Ok(result_type)
+    }
+
+    /// Prefix operators bind as tight as `**`, so `-2 ** 2` is `-(2 ** 2)`.
+    const UNARY_POWER: u8 = 8;
+
+    /// The int literal at the start of `tokens` if a `-` in front of it is
+    /// part of the literal. This is what makes `-9223372036854775808`
+    /// writable, its digits alone do not fit into an int.
+    fn negatable_int(tokens: &[Token]) -> Option<u64> {
+        match tokens {
+            [Token {
+                token: TokenTypes::Number(num),
+                ..
+            }, rest @ ..]
+                if !matches!(
+                    rest.first(),
+                    Some(Token {
+                        token: TokenTypes::Control(ControlVariants::Power),
+                        ..
+                    })
+                ) =>
+            {
+                match num.value() {
+                    Number::Int(int) => Some(int),
+                    Number::Float(_) => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Binding power of the binary operators, from `||` (weakest) to `**`
+    /// (strongest). The flag marks right-associative operators.
+    fn binding_power(operator: &ControlVariants) -> Option<(u8, bool)> {
+        match operator {
+            ControlVariants::Or => Some((1, false)),
+            ControlVariants::And => Some((2, false)),
+            ControlVariants::BitOr => Some((3, false)),
+            ControlVariants::BitAnd => Some((4, false)),
+            ControlVariants::Equal
+            | ControlVariants::NotEqual
+            | ControlVariants::Less
+            | ControlVariants::LessEqual
+            | ControlVariants::Greater
+            | ControlVariants::GreaterEqual => Some((5, false)),
+            ControlVariants::Plus | ControlVariants::Minus => Some((6, false)),
+            ControlVariants::Multiplikation | ControlVariants::Divide => Some((7, false)),
+            ControlVariants::Power => Some((8, true)),
+            _ => None,
+        }
+    }
+
+    /// Parses `condition ? then : otherwise` with `current` on the `?`. The
+    /// ternary binds weaker than every binary operator and nests to the right.
+    fn parse_ternary(
+        &self,
+        tokens: &[Token],
+        current: &mut usize,
+        condition: Expression,
+    ) -> Res<Expression> {
+        let operator = &tokens[*current];
+        *current += 1;
+        if condition.value_type != Type::Bool {
+            return ErrorHandler::compiler_err(
+                ErrorTypes::MismatchedTypes,
+                format!(
+                    "expected bool-type condition but found {}-type",
+                    condition.value_type
+                ),
+                condition.position.clone(),
+            );
+        }
+        let then = self.parse_binary(tokens, current, 0)?;
+        match tokens.get(*current) {
+            Some(Token {
+                token: TokenTypes::Control(ControlVariants::Colon),
+                ..
+            }) => *current += 1,
+            Some(tok) => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::UnexpectedSymbol,
+                    format!("expected ':' but found '{}'", tok.token),
+                    tok.position.clone(),
+                )
+            }
+            None => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    String::from("expected ':' but found nothing"),
+                    operator.position.clone(),
+                )
+            }
+        }
+        let otherwise = self.parse_binary(tokens, current, 0)?;
+        let value_type = if otherwise.value_type.accepts(&then.value_type) {
+            otherwise.value_type.clone()
+        } else {
+            Self::expect_type(&then.value_type, &otherwise)?;
+            then.value_type.clone()
+        };
+        Ok(Expression::new(
+            ExpressionKind::Ternary {
+                condition: Box::new(condition),
+                then: Box::new(then),
+                otherwise: Box::new(otherwise),
+            },
+            value_type,
+            operator.position.clone(),
+        ))
+    }
+
+    fn parse_expression(&self, tokens: &[Token]) -> Res<Expression> {
+        let mut current = 0;
+        let expression = self.parse_binary(tokens, &mut current, 0)?;
+        match tokens.get(current) {
+            Some(token) => ErrorHandler::compiler_err(
+                ErrorTypes::UnexpectedSymbol,
+                format!("expected an operator but found '{}'", token.token),
+                token.position.clone(),
+            ),
+            None => Ok(expression),
+        }
+    }
+
+    fn parse_binary(
+        &self,
+        tokens: &[Token],
+        current: &mut usize,
+        min_power: u8,
+    ) -> Res<Expression> {
+        let mut left = self.parse_primary(tokens, current)?;
+        while let Some(operator) = tokens.get(*current) {
+            let (op, (power, is_right_associative)) = match &operator.token {
+                TokenTypes::Control(op) => match Self::binding_power(op) {
+                    Some(binding) => (op.clone(), binding),
+                    None => break,
+                },
+                _ => break,
+            };
+            if power < min_power {
+                break;
+            }
+            *current += 1;
+            let right = self.parse_binary(
+                tokens,
+                current,
+                if is_right_associative {
+                    power
+                } else {
+                    power + 1
+                },
+            )?;
+            let value_type = Self::binary_type(operator, &left, &right)?;
+            left = Expression::new(
+                ExpressionKind::Binary {
+                    operator: op,
+                    left: Box::new(left),
+                    right: Box::new(right),
+                },
+                value_type,
+                operator.position.clone(),
+            );
+        }
+        if let (
+            0,
+            Some(Token {
+                token: TokenTypes::Control(ControlVariants::Questionmark),
+                ..
+            }),
+        ) = (min_power, tokens.get(*current))
+        {
+            return self.parse_ternary(tokens, current, left);
+        }
+        Ok(left)
+    }
+
+    /// Returns the index of the token closing the bracket at `open`.
+    fn find_closing(tokens: &[Token], open: usize) -> Res<usize> {
+        let (opening, closing, error_type) = match &tokens[open].token {
+            TokenTypes::Control(ControlVariants::OpenParanthesis) => (
+                ControlVariants::OpenParanthesis,
+                ControlVariants::CloseParanthesis,
+                ErrorTypes::UnclosedStatement,
+            ),
+            TokenTypes::Control(ControlVariants::OpenSquare) => (
+                ControlVariants::OpenSquare,
+                ControlVariants::CloseSquare,
+                ErrorTypes::UnclosedStatement,
+            ),
+            TokenTypes::Control(ControlVariants::OpenCurly) => (
+                ControlVariants::OpenCurly,
+                ControlVariants::CloseCurly,
+                ErrorTypes::UnclosedBlock,
+            ),
+            tok => panic!("'{}' does not open a bracket", tok),
+        };
+        let mut depth = 0;
+        for (i, token) in tokens.iter().enumerate().skip(open) {
+            match &token.token {
+                TokenTypes::Control(c) if *c == opening => depth += 1,
+                TokenTypes::Control(c) if *c == closing => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(i);
+                    }
+                }
+                _ => (),
+            }
+        }
+        ErrorHandler::compiler_err(
+            error_type,
+            format!("expected '{}' but found nothing", closing.to_string()),
+            tokens[open].position.clone(),
+        )
+    }
+
+    /// Parses `match value { pattern => expression, ... }` with `current` just
+    /// after the `match` keyword.
+    fn parse_match(
+        &self,
+        tokens: &[Token],
+        current: &mut usize,
+        keyword: &Token,
+    ) -> Res<Expression> {
+        let open = match tokens[*current..]
+            .iter()
+            .position(|tok| matches!(tok.token, TokenTypes::Control(ControlVariants::OpenCurly)))
+        {
+            Some(0) | None => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    String::from("expected 'match value { ... }'"),
+                    keyword.position.clone(),
+                )
+            }
+            Some(offset) => *current + offset,
+        };
+        let value = self.parse_expression(&tokens[*current..open])?;
+        let close = Self::find_closing(tokens, open)?;
+        *current = close + 1;
+
+        let mut arms: Vec<(Option<Literal>, Expression)> = vec![];
+        let mut arm_type: Option<Type> = None;
+        let mut has_wildcard = false;
+        let arm_tokens = Self::split_top_level(&tokens[open + 1..close], ControlVariants::Comma);
+        let arm_count = arm_tokens.len();
+        for (i, arm) in arm_tokens.into_iter().enumerate() {
+            if arm.is_empty() {
+                if i + 1 == arm_count {
+                    break;
+                }
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    String::from("expected a match arm but found nothing"),
+                    tokens[open].position.clone(),
+                );
+            }
+            let arrow = match arm
+                .iter()
+                .position(|tok| matches!(tok.token, TokenTypes::Control(ControlVariants::FatArrow)))
+            {
+                Some(arrow) if arrow > 0 && arrow + 1 < arm.len() => arrow,
+                _ => {
+                    return ErrorHandler::compiler_err(
+                        ErrorTypes::InvalidStatement,
+                        String::from("expected a match arm of the form 'pattern => expression'"),
+                        arm[0].position.clone(),
+                    )
+                }
+            };
+            let pattern = match &arm[..arrow] {
+                [Token {
+                    token: TokenTypes::Name(name),
+                    ..
+                }] if name.to_string() == "_" => None,
+                pattern => {
+                    let pattern = self.parse_expression(pattern)?;
+                    Self::expect_type(&value.value_type, &pattern)?;
+                    match Self::fold_constant(&pattern)? {
+                        Some(literal) => Some(literal),
+                        None => {
+                            return ErrorHandler::compiler_err(
+                                ErrorTypes::InvalidStatement,
+                                format!("expected a constant pattern but found '{}'", pattern),
+                                pattern.position,
+                            )
+                        }
+                    }
+                }
+            };
+            let expression = self.parse_expression(&arm[arrow + 1..])?;
+            match &arm_type {
+                Some(arm_type) => Self::expect_type(arm_type, &expression)?,
+                None => arm_type = Some(expression.value_type.clone()),
+            }
+
+            let is_unreachable = has_wildcard
+                || match &pattern {
+                    Some(literal) => arms
+                        .iter()
+                        .any(|(other, _)| other.as_ref() == Some(literal)),
+                    None => {
+                        value.value_type == Type::Bool
+                            && Self::covers_bool(arms.iter().map(|(pattern, _)| pattern))
+                    }
+                };
+            if is_unreachable {
+                self.diagnostics.report(CompilerError::warning(
+                    ErrorTypes::UnreachablePattern,
+                    String::from("unreachable match arm"),
+                    arm[0].position.clone(),
+                ));
+            }
+            has_wildcard |= pattern.is_none();
+            arms.push((pattern, expression));
+        }
+
+        let is_exhaustive = has_wildcard
+            || (value.value_type == Type::Bool
+                && Self::covers_bool(arms.iter().map(|(pattern, _)| pattern)));
+        if !is_exhaustive {
+            return ErrorHandler::compiler_err(
+                ErrorTypes::NonExhaustiveMatch,
+                match value.value_type {
+                    Type::Bool => {
+                        String::from("expected arms for both 'true' and 'false' or a wildcard '_'")
+                    }
+                    _ => format!(
+                        "expected a wildcard '_' arm for a match on {}-type",
+                        value.value_type
+                    ),
+                },
+                keyword.position.clone(),
+            );
+        }
+        Ok(Expression::new(
+            ExpressionKind::Match {
+                value: Box::new(value),
+                arms,
+            },
+            arm_type.unwrap(),
+            keyword.position.clone(),
+        ))
+    }
+
+    fn covers_bool<'p>(patterns: impl Iterator<Item = &'p Option<Literal>> + Clone) -> bool {
+        [true, false].iter().all(|bool| {
+            patterns
+                .clone()
+                .any(|pattern| *pattern == Some(Literal::Bool(*bool)))
+        })
+    }
+
+    fn parse_primary(&self, tokens: &[Token], current: &mut usize) -> Res<Expression> {
+        let mut expression = self.parse_atom(tokens, current)?;
+        loop {
+            let position = match tokens.get(*current) {
+                Some(Token {
+                    token: TokenTypes::Control(ControlVariants::OpenSquare),
+                    ..
+                }) => {
+                    expression = self.parse_index(tokens, current, expression)?;
+                    continue;
+                }
+                Some(Token {
+                    token: TokenTypes::Control(ControlVariants::Dot),
+                    position,
+                }) => position,
+                _ => break,
+            };
+            let field = match tokens.get(*current + 1) {
+                Some(Token {
+                    token: TokenTypes::Name(field),
+                    ..
+                }) => field.to_string(),
+                Some(tok) => {
+                    return ErrorHandler::compiler_err(
+                        ErrorTypes::UnexpectedSymbol,
+                        format!("expected a field name but found '{}'", tok.token),
+                        tok.position.clone(),
+                    )
+                }
+                None => {
+                    return ErrorHandler::compiler_err(
+                        ErrorTypes::InvalidStatement,
+                        String::from("expected a field name but found nothing"),
+                        position.clone(),
+                    )
+                }
+            };
+            let field_type = match &expression.value_type {
+                Type::Record(name) => self
+                    .get_record(name)
+                    .and_then(|record| record.field_type(&field))
+                    .cloned(),
+                _ => None,
+            };
+            let field_type = match field_type {
+                Some(field_type) => field_type,
+                None => {
+                    return ErrorHandler::compiler_err(
+                        ErrorTypes::UnexpectedSymbol,
+                        format!(
+                            "expected a field, but found no '{}' on {}-type",
+                            field, expression.value_type
+                        ),
+                        tokens[*current + 1].position.clone(),
+                    )
+                }
+            };
+            *current += 2;
+            expression = Expression::new(
+                ExpressionKind::Field {
+                    record: Box::new(expression),
+                    field,
+                },
+                field_type,
+                position.clone(),
+            );
+        }
+        Ok(expression)
+    }
+
+    /// Parses `array[index]` with `current` on the `[`.
+    fn parse_index(
+        &self,
+        tokens: &[Token],
+        current: &mut usize,
+        array: Expression,
+    ) -> Res<Expression> {
+        let open = *current;
+        let close = Self::find_closing(tokens, open)?;
+        *current = close + 1;
+        let element_type = match &array.value_type {
+            Type::Array(element_type) => *element_type.clone(),
+            value_type => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::MismatchedTypes,
+                    format!("cannot index into {}-type", value_type),
+                    tokens[open].position.clone(),
+                )
+            }
+        };
+        let (index, index_span) = match &tokens[open + 1..close] {
+            [] => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    String::from("expected an index but found nothing"),
+                    tokens[open].position.clone(),
+                )
+            }
+            toks => (
+                self.parse_expression(toks)?,
+                toks[0].position.to(&toks[toks.len() - 1].position),
+            ),
+        };
+        Self::expect_type(&Type::Int, &index)?;
+        Ok(Expression::new(
+            ExpressionKind::Index {
+                array: Box::new(array),
+                index: Box::new(index),
+                index_span,
+            },
+            element_type,
+            tokens[open].position.clone(),
+        ))
+    }
+
+    /// Parses `[element, ...]` with `current` just after the `[`. The element
+    /// type is the one of the first element, `[]` is a `void[]`.
+    fn parse_array(&self, tokens: &[Token], current: &mut usize, open: &Token) -> Res<Expression> {
+        let close = Self::find_closing(tokens, *current - 1)?;
+        let elements = self.parse_list(&tokens[*current..close])?;
+        *current = close + 1;
+        let mut element_type = Type::Void;
+        for element in &elements {
+            if element.value_type == Type::Void {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::MismatchedTypes,
+                    String::from("expected a value but found a void-type"),
+                    element.position.clone(),
+                );
+            }
+            if !element_type.accepts(&element.value_type) {
+                match &element_type {
+                    Type::Void => element_type = element.value_type.clone(),
+                    Type::Array(inner) if **inner == Type::Void => {
+                        element_type = element.value_type.clone()
+                    }
+                    _ => Self::expect_type(&element_type, element)?,
+                }
+            }
+        }
+        Ok(Expression::new(
+            ExpressionKind::Array(elements),
+            Type::Array(Box::new(element_type)),
+            open.position.clone(),
+        ))
+    }
+
+    /// Parses `int(value)` or `float(value)` with `current` just after the type.
+    /// Floats are truncated towards zero when converted to ints.
+    fn parse_conversion(
+        &self,
+        tokens: &[Token],
+        current: &mut usize,
+        keyword: &Token,
+    ) -> Res<Expression> {
+        let target = Type::from_token(&keyword.token).unwrap();
+        let open = match tokens.get(*current) {
+            Some(Token {
+                token: TokenTypes::Control(ControlVariants::OpenParanthesis),
+                ..
+            }) => *current,
+            _ => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    format!("expected '{}(value)' to convert a number", target),
+                    keyword.position.clone(),
+                )
+            }
+        };
+        let close = Self::find_closing(tokens, open)?;
+        *current = close + 1;
+        let value = match &self.parse_list(&tokens[open + 1..close])?[..] {
+            [value] => value.clone(),
+            arguments => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::MismatchedTypes,
+                    format!(
+                        "expected 1 arguments for '{}' but found {}",
+                        target,
+                        arguments.len()
+                    ),
+                    keyword.position.clone(),
+                )
+            }
+        };
+        if !matches!(value.value_type, Type::Int | Type::Float) {
+            return ErrorHandler::compiler_err(
+                ErrorTypes::MismatchedTypes,
+                format!(
+                    "cannot convert a {}-type to {}-type",
+                    value.value_type, target
+                ),
+                value.position,
+            );
+        }
+        Ok(Expression::new(
+            ExpressionKind::Conversion(Box::new(value)),
+            target,
+            keyword.position.clone(),
+        ))
+    }
+
+    /// Parses the expressions embedded in an interpolated string. They may be
+    /// of any type but void and are converted to str when the string is built.
+    fn parse_interpolation(
+        &self,
+        interpolation: &InterpolationVariants,
+        token: &Token,
+    ) -> Res<Expression> {
+        let mut parts = vec![];
+        for part in interpolation.parts() {
+            parts.push(match part {
+                InterpolationPart::Text(text) => Expression::new(
+                    ExpressionKind::Literal(Literal::Str(text.clone())),
+                    Type::Str,
+                    token.position.clone(),
+                ),
+                InterpolationPart::Expression { tokens, .. } => {
+                    let expression = self.parse_expression(tokens)?;
+                    if expression.value_type == Type::Void {
+                        return ErrorHandler::compiler_err(
+                            ErrorTypes::MismatchedTypes,
+                            String::from("cannot interpolate a void-type into a str"),
+                            expression.position,
+                        );
+                    }
+                    expression
+                }
+            });
+        }
+        Ok(Expression::new(
+            ExpressionKind::Interpolation(parts),
+            Type::Str,
+            token.position.clone(),
+        ))
+    }
+
+    /// Parses `Name { field: value, ... }` with `current` on the `{`.
+    fn parse_record_construction(
+        &self,
+        tokens: &[Token],
+        current: &mut usize,
+        name: &Token,
+    ) -> Res<Expression> {
+        let record = self.get_record(&name.token.to_string()).unwrap();
+        let open = *current;
+        let close = Self::find_closing(tokens, open)?;
+        *current = close + 1;
+        let mut values: Vec<(String, Expression, Position)> = vec![];
+        for field in Self::split_top_level(&tokens[open + 1..close], ControlVariants::Comma) {
+            let (field_name, position, value) = match field {
+                [] => continue,
+                [Token {
+                    token: TokenTypes::Name(field_name),
+                    position,
+                }, Token {
+                    token: TokenTypes::Control(ControlVariants::Colon),
+                    ..
+                }, value @ ..]
+                    if !value.is_empty() =>
+                {
+                    (field_name.to_string(), position, value)
+                }
+                toks => {
+                    return ErrorHandler::compiler_err(
+                        ErrorTypes::InvalidStatement,
+                        String::from("expected a field of the form 'name: value'"),
+                        toks[0].position.clone(),
+                    )
+                }
+            };
+            let field_type = match record.field_type(&field_name) {
+                Some(field_type) => field_type,
+                None => {
+                    return ErrorHandler::compiler_err(
+                        ErrorTypes::UnexpectedSymbol,
+                        format!(
+                            "expected a field, but found no '{}' on {}-type",
+                            field_name, record.name
+                        ),
+                        position.clone(),
+                    )
+                }
+            };
+            if let Some((_, _, previous)) = values.iter().find(|(name, _, _)| *name == field_name) {
+                return Err(CompilerError::new(
+                    ErrorTypes::DuplicateDefinition,
+                    format!("'{}' is already defined in this scope", field_name),
+                    position.clone(),
+                )
+                .with_label(
+                    previous.clone(),
+                    format!("previous definition of '{}' here", field_name),
+                )
+                .into());
+            }
+            let value = self.parse_expression(value)?;
+            Self::expect_type(field_type, &value)?;
+            values.push((field_name, value, position.clone()));
+        }
+        let mut fields = vec![];
+        for (field_name, _) in &record.fields {
+            match values.iter().position(|(name, _, _)| name == field_name) {
+                Some(i) => fields.push((field_name.clone(), values.remove(i).1)),
+                None => {
+                    return ErrorHandler::compiler_err(
+                        ErrorTypes::InvalidStatement,
+                        format!("expected a value for field '{}'", field_name),
+                        tokens[open].position.clone(),
+                    )
+                }
+            }
+        }
+        Ok(Expression::new(
+            ExpressionKind::Record {
+                name: record.name.clone(),
+                fields,
+            },
+            Type::Record(record.name.clone()),
+            name.position.clone(),
+        ))
+    }
+
+    fn parse_atom(&self, tokens: &[Token], current: &mut usize) -> Res<Expression> {
+        let token = match tokens.get(*current) {
+            Some(token) => token,
+            None => {
+                return ErrorHandler::compiler_err(
+                    ErrorTypes::InvalidStatement,
+                    String::from("expected an expression, but found nothing"),
+                    tokens.last().unwrap().position.clone(),
+                )
+            }
+        };
+        *current += 1;
+        match &token.token {
+            TokenTypes::Word(WordVariants::Match) => {
+                return self.parse_match(tokens, current, token)
+            }
+            TokenTypes::Control(ControlVariants::OpenSquare) => {
+                return self.parse_array(tokens, current, token)
+            }
+            TokenTypes::Interpolation(interpolation) => {
+                return self.parse_interpolation(interpolation, token)
+            }
+            TokenTypes::Word(WordVariants::Int | WordVariants::Float) => {
+                return self.parse_conversion(tokens, current, token)
+            }
+            TokenTypes::Control(ControlVariants::OpenParanthesis) => {
+                let expression = self.parse_binary(tokens, current, 0)?;
+                match tokens.get(*current) {
+                    Some(Token {
+                        token: TokenTypes::Control(ControlVariants::CloseParanthesis),
+                        ..
+                    }) => {
+                        *current += 1;
+                        return Ok(expression);
+                    }
+                    Some(tok) => {
+                        return ErrorHandler::compiler_err(
+                            ErrorTypes::UnexpectedSymbol,
+                            format!("expected ')' but found '{}'", tok.token),
+                            tok.position.clone(),
+                        )
+                    }
+                    None => {
+                        return ErrorHandler::compiler_err(
+                            ErrorTypes::UnclosedStatement,
+                            String::from("expected ')' but found nothing"),
+                            token.position.clone(),
+                        )
+                    }
+                }
+            }
+            TokenTypes::Control(op @ (ControlVariants::Minus | ControlVariants::Not)) => {
+                if let (ControlVariants::Minus, Some(int)) =
+                    (op, Self::negatable_int(&tokens[*current..]))
+                {
+                    let position = token.position.to(&tokens[*current].position);
+                    *current += 1;
+                    return Ok(Expression::new(
+                        ExpressionKind::Literal(Literal::Int(0i64.wrapping_sub_unsigned(int))),
+                        Type::Int,
+                        position,
+                    ));
+                }
+                let operand = self.parse_binary(tokens, current, Self::UNARY_POWER)?;
+                let value_type = match (op, &operand.value_type) {
+                    (ControlVariants::Minus, Type::Int | Type::Float) => operand.value_type.clone(),
+                    (ControlVariants::Not, Type::Bool) => Type::Bool,
+                    _ => {
+                        return ErrorHandler::compiler_err(
+                            ErrorTypes::MismatchedTypes,
+                            format!(
+                                "operator '{}' cannot be applied to {}-type",
+                                op.to_string(),
+                                operand.value_type
+                            ),
+                            operand.position.clone(),
+                        )
+                    }
+                };
+                return Ok(Expression::new(
+                    ExpressionKind::Unary {
+                        operator: op.clone(),
+                        operand: Box::new(operand),
+                    },
+                    value_type,
+                    token.position.clone(),
+                ));
+            }
+            TokenTypes::Name(name) => {
+                if let Some(Token {
+                    token: TokenTypes::Control(ControlVariants::OpenParanthesis),
+                    ..
+                }) = tokens.get(*current)
+                {
+                    let start = *current - 1;
+                    *current = Self::find_closing(tokens, *current)? + 1;
+                    return self.parse_function_call(&tokens[start..*current]);
+                }
+                if let Some(Token {
+                    token: TokenTypes::Control(ControlVariants::OpenCurly),
+                    ..
+                }) = tokens.get(*current)
+                {
+                    if self.get_record(&name.to_string()).is_some() {
+                        return self.parse_record_construction(tokens, current, token);
+                    }
+                }
+                return match self.get_variable(&name.to_string()) {
+                    Some(Variable {
+                        constant: Some(literal),
+                        var_type,
+                        ..
+                    }) => Ok(Expression::new(
+                        ExpressionKind::Literal(literal.clone()),
+                        var_type.clone(),
+                        token.position.clone(),
+                    )),
+                    Some(variable) => Ok(Expression::new(
+                        ExpressionKind::Identifier(name.to_string()),
+                        variable.var_type.clone(),
+                        token.position.clone(),
+                    )),
+                    None => ErrorHandler::compiler_err(
+                        ErrorTypes::UnexpectedSymbol,
+                        format!(
+                            "expected a variable name, but found no '{}' in this scope",
+                            token.token
+                        ),
+                        token.position.clone(),
+                    ),
+                };
+            }
+            _ => (),
+        }
+        if let TokenTypes::Number(num) = &token.token {
+            if Literal::from_token(&token.token).is_none() {
+                return Err(CompilerError::new(
+                    ErrorTypes::UnexpectedSymbol,
+                    format!("the number '{}' is too large for an int", num.to_string()),
+                    token.position.clone(),
+                )
+                .with_help(format!("only '-{}' is a valid int", num.to_string()))
+                .into());
+            }
+        }
+        match Literal::from_token(&token.token) {
+            Some(literal) => Ok(Expression::new(
+                ExpressionKind::Literal(literal.clone()),
+                literal.value_type(),
+                token.position.clone(),
+            )),
+            None => ErrorHandler::compiler_err(
+                ErrorTypes::InvalidStatement,
+                format!("expected an expression but found '{}'", token.token),
+                token.position.clone(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::tokenizer;
+    use std::io::BufReader;
+
+    /// The codes of all diagnostics reported for `source`, in order.
+    fn errors(source: &str) -> Vec<&'static str> {
+        let diagnostics = Diagnostics::new();
+        let tokens = tokenizer(BufReader::new(source.as_bytes()), "test", &diagnostics).unwrap();
+        Scope::new(&diagnostics).parse_block(tokens).unwrap();
+        diagnostics
+            .into_errors()
+            .iter()
+            .map(|error| error.error_type().code())
+            .collect()
+    }
+
+    /// The parenthesized value of the declaration that ends `source`.
+    fn expression(source: &str) -> String {
+        let diagnostics = Diagnostics::new();
+        let tokens = tokenizer(BufReader::new(source.as_bytes()), "test", &diagnostics).unwrap();
+        let statements = Scope::new(&diagnostics).parse_block(tokens).unwrap();
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics.into_errors());
+        match &statements.last().unwrap().kind {
+            StatementKind::Declaration { value, .. } => value.to_string(),
+            statement => panic!("expected a declaration but found {:?}", statement),
+        }
+    }
+
+    #[test]
+    fn binary_operator_precedence() {
+        assert_eq!(expression("int x = 1 + 2 * 3;"), "(1 + (2 * 3))");
+        assert_eq!(expression("int x = 1 * 2 + 3;"), "((1 * 2) + 3)");
+        assert_eq!(
+            expression("bool x = 1 + 2 < 4 && 3 > 2 || false;"),
+            "((((1 + 2) < 4) && (3 > 2)) || false)"
+        );
+    }
+
+    #[test]
+    fn binary_operator_associativity() {
+        assert_eq!(expression("int x = 10 - 3 - 2;"), "((10 - 3) - 2)");
+        assert_eq!(expression("int x = 8 / 4 / 2;"), "((8 / 4) / 2)");
+    }
+
+    #[test]
+    fn binary_operands_are_type_checked() {
+        assert_eq!(errors("int x = 1 + true;"), ["E0006"]);
+        assert_eq!(errors("bool x = 1 < true;"), ["E0006"]);
+        assert_eq!(errors("int x = 1 +;"), ["E0004"]);
+    }
+
+    #[test]
+    fn parentheses_and_unary_operators() {
+        assert_eq!(expression("int x = (1 + 2) * 3;"), "((1 + 2) * 3)");
+        assert_eq!(expression("int x = -(2 + 3) * 2;"), "((-(2 + 3)) * 2)");
+        assert_eq!(expression("bool x = !true && false;"), "((!true) && false)");
+        assert_eq!(expression("bool x = !!(1 < 2);"), "(!(!(1 < 2)))");
+        assert_eq!(errors("int x = -true;"), ["E0006"]);
+        assert_eq!(errors("bool b = !1;"), ["E0006"]);
+        assert_eq!(errors("int x = (1 + 2;"), ["E0005"]);
+        assert_eq!(errors("int x = ();"), ["E0004"]);
+    }
+
+    #[test]
+    fn variable_references() {
+        assert_eq!(
+            expression("int y = 1;\nint x = y * (y + 1);"),
+            "(y * (y + 1))"
+        );
+        assert_eq!(errors("int x = y + 1;"), ["E0005"]);
+        assert_eq!(errors("x = 1;"), ["E0005"]);
+        assert_eq!(errors("int y = 1;\nstr x = y;"), ["E0006"]);
+        assert_eq!(errors("int y = 1;\ny = \"a\";"), ["E0006"]);
+    }
+
+    #[test]
+    fn scopes_and_shadowing() {
+        assert!(errors("int y = 1;\n{ str y = \"a\"; print(y); };\nint z = y;").is_empty());
+        assert!(errors("int y = 1;\n{ { y = 2; }; };").is_empty());
+        assert_eq!(errors("{ int y = 1; };\nprint(y);"), ["E0005"]);
+        assert_eq!(errors("int y = 1;\nint y = 2;"), ["E0007"]);
+        assert_eq!(errors("{ int y = 1; int y = 2; };"), ["E0007"]);
+    }
+
+    #[test]
+    fn if_statements() {
+        assert!(
+            errors("if (true) { print(1); } else if (false) { } else { print(3); };").is_empty()
+        );
+        assert_eq!(errors("if (1) { };"), ["E0006"]);
+        assert_eq!(errors("if true { };"), ["E0004"]);
+        assert_eq!(errors("if (true) { int a = 1; };\nprint(a);"), ["E0005"]);
+    }
+
+    #[test]
+    fn while_loops() {
+        assert!(errors("while (true) { if (true) { break; }; continue; };").is_empty());
+        assert_eq!(errors("while (1) { };"), ["E0006"]);
+        assert_eq!(errors("break;"), ["E0004"]);
+        assert_eq!(errors("continue;"), ["E0004"]);
+    }
+
+    #[test]
+    fn for_loops() {
+        assert!(errors("for (;;) { break; };").is_empty());
+        assert!(errors("for (int i = 0; i < 3; i = i + 1) { continue; };").is_empty());
+        assert_eq!(errors("for (int i = 0; i; i = i + 1) { };"), ["E0006"]);
+        assert_eq!(
+            errors("for (int i = 0; i < 3; i = i + 1) { };\nprint(i);"),
+            ["E0005"]
+        );
+    }
+
+    #[test]
+    fn function_arguments_are_checked() {
+        let f = "func f args(int a, str b): int { return a; };\n";
+        assert!(errors(&format!("{}int x = f(1, \"b\");", f)).is_empty());
+        assert_eq!(errors(&format!("{}int x = f(1);", f)), ["E0006"]);
+        assert_eq!(errors(&format!("{}int x = f(1, \"b\", 3);", f)), ["E0006"]);
+        assert_eq!(errors(&format!("{}int x = f(\"a\", \"b\");", f)), ["E0006"]);
+        assert_eq!(errors(&format!("{}str x = f(1, \"b\");", f)), ["E0006"]);
+        assert_eq!(errors("int x = g(1);"), ["E0005"]);
+        assert_eq!(errors("func f args(int a, int a) { };"), ["E0007"]);
+    }
+
+    #[test]
+    fn return_paths_are_checked() {
+        assert!(errors("func f: int { if (true) { return 1; } else { return 2; }; };").is_empty());
+        assert!(errors("func f: int { { return 1; }; };").is_empty());
+        assert!(errors("func f { if (true) { return; }; };").is_empty());
+        assert_eq!(
+            errors("func f: int { if (true) { return 1; }; };"),
+            ["E0006"]
+        );
+        assert_eq!(
+            errors("func f: int { while (true) { return 1; }; };"),
+            ["E0006"]
+        );
+        assert_eq!(errors("func f { return 1; };"), ["E0006"]);
+        assert_eq!(errors("func f: int { return; };"), ["E0006"]);
+        assert_eq!(errors("return 1;"), ["E0004"]);
+    }
+
+    #[test]
+    fn constants_are_folded() {
+        assert_eq!(expression("const int X = 2 * 3 + 1;"), "7");
+        assert_eq!(
+            expression("const int X = 2 * 3 + 1;\nconst int Y = X * 2;"),
+            "14"
+        );
+        assert_eq!(expression("const bool B = !(1 < 2) || true;"), "true");
+        assert_eq!(errors("int y = 1;\nconst int X = y;"), ["E0004"]);
+    }
+
+    #[test]
+    fn constants_are_immutable() {
+        assert_eq!(errors("const int X = 1;\nX = 2;"), ["E0008"]);
+        assert_eq!(errors("const int X = 1;\n{ X = 2; };"), ["E0008"]);
+        assert!(errors("const int X = 1;\n{ int X = 2; X = 3; };").is_empty());
+    }
+
+    #[test]
+    fn match_must_be_exhaustive() {
+        let x = "int x = 1;\nbool b = true;\n";
+        assert!(errors(&format!("{}int y = match x {{ 1 => 1, _ => 2 }};", x)).is_empty());
+        assert!(errors(&format!(
+            "{}int y = match b {{ true => 1, false => 2 }};",
+            x
+        ))
+        .is_empty());
+        assert_eq!(
+            errors(&format!("{}str s = match x {{ 1 => \"a\" }};", x)),
+            ["E0009"]
+        );
+        assert_eq!(
+            errors(&format!("{}int y = match b {{ true => 1 }};", x)),
+            ["E0009"]
+        );
+        assert_eq!(
+            errors(&format!("{}int y = match x {{ 1 => 1, _ => \"a\" }};", x)),
+            ["E0006"]
+        );
+        assert_eq!(
+            errors(&format!("{}int y = match x {{ \"a\" => 1, _ => 2 }};", x)),
+            ["E0006"]
+        );
+        assert_eq!(
+            errors(&format!("{}int y = match x {{ x => 1, _ => 2 }};", x)),
+            ["E0004"]
+        );
+    }
+
+    #[test]
+    fn records() {
+        let p = "def P { int x, int y };\n";
+        assert!(errors(&format!("{}P p = P {{ y: 2, x: 1 }};\np.x = p.y;", p)).is_empty());
+        assert_eq!(errors(&format!("{}P p = P {{ x: 1 }};", p)), ["E0004"]);
+        assert_eq!(
+            errors(&format!("{}P p = P {{ x: 1, y: \"a\" }};", p)),
+            ["E0006"]
+        );
+        assert_eq!(
+            errors(&format!("{}P p = P {{ x: 1, y: 2, z: 3 }};", p)),
+            ["E0005"]
+        );
+        assert_eq!(
+            errors(&format!("{}P p = P {{ x: 1, y: 2 }};\nint z = p.z;", p)),
+            ["E0005"]
+        );
+        assert_eq!(errors("def P { int x, int x };"), ["E0007"]);
+        assert_eq!(errors("def P { int x };\ndef P { int y };"), ["E0007"]);
+    }
+
+    #[test]
+    fn arrays() {
+        assert!(errors("int[][] m = [[1, 2], [3]];\nm[1][0] = len(m[0]);").is_empty());
+        assert!(errors("int[] a = [];\nint n = len(a) + len(\"abc\");").is_empty());
+        assert_eq!(errors("int[] a = [1, \"a\"];"), ["E0006"]);
+        assert_eq!(errors("int[] a = [1];\nstr x = a[0];"), ["E0006"]);
+        assert_eq!(errors("int[] a = [1];\nint x = a[\"0\"];"), ["E0006"]);
+        assert_eq!(errors("int x = 1;\nint y = x[0];"), ["E0006"]);
+        assert_eq!(errors("int n = len(1);"), ["E0006"]);
+    }
+
+    #[test]
+    fn for_each_loops() {
+        assert!(errors("for (int v : [1, 2]) { if (v < 2) { continue; }; };").is_empty());
+        assert_eq!(errors("for (int v : 5) { };"), ["E0006"]);
+        assert_eq!(errors("for (str v : [1, 2]) { };"), ["E0006"]);
+        assert_eq!(errors("for (int v : [1, 2]) { };\nprint(v);"), ["E0005"]);
+    }
+
+    #[test]
+    fn ternaries() {
+        assert_eq!(
+            expression("bool c = true;\nint x = c ? 1 : c ? 2 : 3;"),
+            "(c ? 1 : (c ? 2 : 3))"
+        );
+        assert_eq!(
+            expression("int x = 1 < 2 ? 1 + 1 : 3;"),
+            "((1 < 2) ? (1 + 1) : 3)"
+        );
+        assert_eq!(expression("const bool B = 1 < 2 ? !false : false;"), "true");
+        assert_eq!(errors("int x = 1 ? 2 : 3;"), ["E0006"]);
+        assert_eq!(errors("int x = true ? 2 : \"a\";"), ["E0006"]);
+        assert_eq!(errors("int x = true ? 1;"), ["E0004"]);
+    }
+
+    #[test]
+    fn multi_character_operators() {
+        assert_eq!(expression("int x = 2 * 3 ** 2;"), "(2 * (3 ** 2))");
+        assert_eq!(expression("int x = 2 ** 3 ** 2;"), "(2 ** (3 ** 2))");
+        assert_eq!(expression("int y = 2;\nint x = -y ** 2;"), "(-(y ** 2))");
+        assert_eq!(
+            expression("bool x = 1 + 2 <= 4 && 3 == 3 || 1 != 1;"),
+            "((((1 + 2) <= 4) && (3 == 3)) || (1 != 1))"
+        );
+    }
+
+    #[test]
+    fn ints_and_floats_do_not_mix() {
+        assert!(errors("int x = int(1.9);\nfloat y = float(x) / 2.0;").is_empty());
+        assert!(errors("int x = 5 & 3 | 8;").is_empty());
+        assert_eq!(errors("int x = 1 + 1.0;"), ["E0006"]);
+        assert_eq!(errors("float x = 1;"), ["E0006"]);
+        assert_eq!(errors("bool b = 1 < 1.5;"), ["E0006"]);
+        assert_eq!(errors("float f = 1.5 & 2.5;"), ["E0006"]);
+        assert_eq!(errors("str s = 1 + \"a\";"), ["E0006"]);
+        assert_eq!(errors("int[] a = [1];\nint x = a[0.5];"), ["E0006"]);
+    }
+
+    #[test]
+    fn errors_of_all_statements_are_collected() {
+        assert_eq!(
+            errors("int x = true;\nstr y = 1;\nz = 2;\nint w = x;"),
+            ["E0006", "E0006", "E0005"]
+        );
+        assert_eq!(
+            errors("{ int a = \"a\"; };\nfunc f { bool b = 1; };\nint c = 1 +;"),
+            ["E0006", "E0006", "E0004"]
+        );
+    }
+
+    #[test]
+    fn malformed_tokens_cause_no_follow_up_errors() {
+        assert_eq!(errors("int x = 1__0;"), ["E0005"]);
+        assert_eq!(errors("str greeting = \"Hello;"), ["E0001"]);
+        assert_eq!(errors("print(\"x {\");"), ["E0001"]);
+        assert_eq!(errors("int x = 1__0;\nprint(x);"), ["E0005"]);
+        assert_eq!(errors("print(1); /* open"), ["E0010"]);
+    }
+
+    #[test]
+    fn recovered_return_counts_as_returning() {
+        assert_eq!(errors("func f: int { return true; };"), ["E0006"]);
+        assert_eq!(errors("func f: int { print(1); };"), ["E0006"]);
+    }
+
+    #[test]
+    fn functions_take_an_optional_parameter_list() {
+        assert!(errors("func f(): int { return 1; };\nint x = f();").is_empty());
+        assert!(errors("func f: int { return 1; };\nint x = f();").is_empty());
+        assert!(
+            errors("func f(int a, float b): int { return a; };\nint x = f(1, 2.0);").is_empty()
+        );
+        assert!(errors("func f args(int a) { print(a); };\nf(1);").is_empty());
+    }
+
+    #[test]
+    fn forward_declarations_allow_mutual_recursion() {
+        let source = "func odd args(int n): bool;
+            func even args(int n): bool { if (n == 0) { return true; }; return odd(n - 1); };
+            func odd args(int n): bool { if (n == 0) { return false; }; return even(n - 1); };";
+        assert!(errors(source).is_empty());
+        assert_eq!(errors("func f: int;"), ["E0004"]);
+        assert_eq!(
+            errors("func f args(int n): bool;\nfunc f args(float n): bool { return true; };"),
+            ["E0006"]
+        );
+        assert_eq!(
+            errors("func f: int { return 1; };\nfunc f: int;"),
+            ["E0007"]
+        );
+    }
+
+    #[test]
+    fn constants_report_failed_folding() {
+        assert_eq!(errors("const int X = 1 / 0;\nprint(X);"), ["E0012"]);
+        assert_eq!(errors("const int X = 2 ** 63;"), ["E0012"]);
+        assert_eq!(errors("const int X = int(1e30);"), ["E0012"]);
+        assert_eq!(errors("int y = 1;\nconst int X = y;\nprint(X);"), ["E0004"]);
+    }
+
+    #[test]
+    fn smallest_int_literal() {
+        assert!(errors("int x = -9223372036854775808;").is_empty());
+        assert!(errors("const int X = -9223372036854775808;").is_empty());
+        assert!(errors("int x = 1 - -0x8000_0000_0000_0000;").is_empty());
+        assert_eq!(errors("int x = 9223372036854775808;"), ["E0005"]);
+        assert_eq!(errors("int x = -(9223372036854775808);"), ["E0005"]);
+        assert_eq!(errors("int x = -9223372036854775808 ** 1;"), ["E0005"]);
+    }
+
+    #[test]
+    fn unreachable_match_arms_are_warnings() {
+        assert_eq!(
+            errors("int x = 1;\nstr s = match x { _ => \"a\", 1 => \"b\" };"),
+            ["E0013"]
+        );
+        assert_eq!(
+            errors("bool b = true;\nint x = match b { true => 1, false => 2, _ => 3 };"),
+            ["E0013"]
+        );
+        assert_eq!(
+            errors("int x = 1;\nint y = match x { 1 => 1, 1 => 2, _ => 3 };"),
+            ["E0013"]
+        );
+    }
+
+    #[test]
+    fn stray_parenthesis_does_not_hide_later_errors() {
+        assert_eq!(errors("print(1));\nint x = \"a\";"), ["E0005", "E0006"]);
+        assert_eq!(
+            errors("{ int y = 1); };\nint x = \"a\";"),
+            ["E0005", "E0006"]
+        );
     }
 }
@@ -1,83 +1,755 @@
 use std::{io::BufRead, io::BufReader};
 
-use ops::{is_enum_token_variant, ErrorHandler, ErrorTypes, Position, Res, Token};
+use ops::{
+    CompilerError, ControlVariants, Diagnostics, ErrorTypes, InterpolationPart,
+    InterpolationVariants, NumberVariants, Position, Res, Token, TokenTypes, TokenVariant,
+    MAX_CONTROL_LENGTH,
+};
 
-pub fn tokenizer<R>(source: BufReader<R>, file_name: &str) -> Res<Vec<Token>>
+/// Returns the longest control symbol `line` starts with, so `<=` wins over `<`.
+fn control_symbol(line: &str) -> Option<&str> {
+    line.char_indices()
+        .take(MAX_CONTROL_LENGTH)
+        .map(|(i, c)| &line[..i + c.len_utf8()])
+        .filter(|symbol| ControlVariants::new(symbol).is_some())
+        .last()
+}
+
+/// Whether `c` continues the number literal in `current` although it is a
+/// control symbol, like the '.' of `1.5` or the sign of `1e-5`.
+fn continues_number(current: &str, c: char) -> bool {
+    if !current.starts_with(|c: char| c.is_ascii_digit())
+        || ["0x", "0o", "0b"]
+            .iter()
+            .any(|prefix| current.starts_with(prefix))
+    {
+        return false;
+    }
+    match c {
+        '.' => current.chars().all(|c| c.is_ascii_digit() || c == '_'),
+        '+' | '-' => {
+            current.ends_with(['e', 'E'])
+                && current[..current.len() - 1]
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || c == '_' || c == '.')
+        }
+        _ => false,
+    }
+}
+
+/// Resolves the escape sequence following a '\\' at the start of `rest`,
+/// returning the character and the number of bytes the sequence spans.
+fn escape_sequence(rest: &str) -> Option<(char, usize)> {
+    let c = match rest.chars().next()? {
+        '"' => '"',
+        '\\' => '\\',
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        '0' => '\0',
+        'u' => {
+            let hex = rest.strip_prefix("u{")?;
+            let close = hex.find('}')?;
+            let hex = &hex[..close];
+            if hex.is_empty() || hex.len() > 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return None;
+            }
+            return u32::from_str_radix(hex, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .map(|c| (c, close + 3));
+        }
+        _ => return None,
+    };
+    Some((c, 1))
+}
+
+/// The length of the `{`, up to 6 hex digits and `}` that follow a malformed
+/// `\u`, so the rest of the string is not swallowed by the error.
+fn unicode_escape_length(rest: &str) -> usize {
+    let Some(hex) = rest.strip_prefix('{') else {
+        return 0;
+    };
+    let digits = hex
+        .chars()
+        .take(6)
+        .take_while(char::is_ascii_hexdigit)
+        .count();
+    1 + digits + usize::from(hex[digits..].starts_with('}'))
+}
+
+/// An expression embedded in a string literal that is still being read.
+struct Interpolation {
+    source: String,
+    start: Position,
+    depth: usize,
+    is_in_string: bool,
+    is_escaped: bool,
+}
+
+/// Tokenizes `source`, malformed tokens are reported to `diagnostics` and
+/// kept as invalid tokens, so one run reports all of them.
+pub fn tokenizer<R>(
+    source: BufReader<R>,
+    file_name: &str,
+    diagnostics: &Diagnostics,
+) -> Res<Vec<Token>>
+where
+    R: std::io::Read,
+{
+    tokenize(
+        source,
+        &Position::new(file_name.to_string(), 1, 1),
+        diagnostics,
+    )
+}
+
+/// Tokenizes `source` as if it started at `origin`, which lets embedded
+/// expressions of interpolated strings report positions in the whole file.
+fn tokenize<R>(
+    source: BufReader<R>,
+    origin: &Position,
+    diagnostics: &Diagnostics,
+) -> Res<Vec<Token>>
 where
     R: std::io::Read,
 {
+    let lines = source.lines().collect::<Result<Vec<String>, _>>()?;
+    // The tokenizer works on byte offsets, positions count characters.
+    let char_column = |(row, column): (usize, usize)| {
+        let line = row.checked_sub(1).and_then(|row| lines.get(row));
+        let line = line.map_or("", |line| line.as_str());
+        match line.get(..column.saturating_sub(1)) {
+            Some(prefix) => (row, prefix.chars().count() + 1),
+            None => (
+                row,
+                line.chars().count() + column.saturating_sub(line.len()),
+            ),
+        }
+    };
+    let span = |start: (usize, usize), end: (usize, usize)| {
+        Position::span(String::new(), char_column(start), char_column(end)).relative_to(origin)
+    };
     let mut tokens: Vec<Token> = vec![];
 
     let mut current_token_value: String = String::from("");
     let mut is_in_string: bool = false;
     let mut is_new_token: bool = true;
-    let mut add_token =
-        |current_token_value: String, row: usize, col: usize, is_in_string: bool| {
-            let current_position = Position::new(file_name.to_string(), row, col);
-            tokens.push(Token::new(
+    let new_token = |current_token_value: String,
+                     start: (usize, usize),
+                     end: (usize, usize),
+                     is_in_string: bool|
+     -> Token {
+        let current_position = span(start, end);
+        if let Some(token) = Token::new(
+            current_token_value.clone(),
+            current_position.clone(),
+            is_in_string,
+        ) {
+            return token;
+        }
+        let message = if current_token_value.starts_with(|c: char| c.is_ascii_digit()) {
+            format!(
+                "malformed number '{}', {}",
                 current_token_value,
-                current_position,
-                is_in_string,
-            ));
+                NumberVariants::parse(&current_token_value).unwrap_err()
+            )
+        } else {
+            format!(
+                "expected a name of letters, digits and '_' but found '{}'",
+                current_token_value
+            )
         };
-    let mut string_start_row = 0;
-    let mut string_start_col = 0;
-    let lines = source.lines();
-    for (row, line) in lines.enumerate() {
-        let line = line?;
+        diagnostics.report(CompilerError::new(
+            ErrorTypes::UnexpectedSymbol,
+            message,
+            current_position.clone(),
+        ));
+        Token::invalid(&current_token_value, current_position)
+    };
+    // Where the pending token and the pending string start, as row and column.
+    let mut token_start = (0, 0);
+    let mut string_start = (0, 0);
+    let mut string_parts: Vec<InterpolationPart> = vec![];
+    let mut interpolation: Option<Interpolation> = None;
+    // Block comments nest, so `/* /* */ */` is a single comment.
+    let mut comment_depth: usize = 0;
+    let mut comment_start = (0, 0);
+    for (row, line) in lines.iter().enumerate() {
+        let mut skip_until = 0;
         for (col, c) in line.char_indices() {
+            if col < skip_until {
+                continue;
+            }
+            if let Some(embedded) = &mut interpolation {
+                if embedded.is_in_string {
+                    if embedded.is_escaped {
+                        embedded.is_escaped = false;
+                    } else if c == '\\' {
+                        embedded.is_escaped = true;
+                    } else if c == '"' {
+                        embedded.is_in_string = false;
+                    }
+                } else {
+                    match c {
+                        '"' => embedded.is_in_string = true,
+                        '{' => embedded.depth += 1,
+                        '}' if embedded.depth > 0 => embedded.depth -= 1,
+                        '}' => {
+                            let embedded = interpolation.take().unwrap();
+                            let reported = diagnostics.len();
+                            let embedded_tokens = tokenize(
+                                BufReader::new(embedded.source.as_bytes()),
+                                &embedded.start,
+                                diagnostics,
+                            )?;
+                            if embedded_tokens.is_empty() {
+                                // Only blank if nothing was skipped as malformed.
+                                if diagnostics.len() == reported {
+                                    diagnostics.report(CompilerError::new(
+                                        ErrorTypes::InvalidStatement,
+                                        String::from("expected an expression between '{' and '}'"),
+                                        embedded.start,
+                                    ));
+                                }
+                                continue;
+                            }
+                            string_parts.push(InterpolationPart::Expression {
+                                tokens: embedded_tokens,
+                                position: embedded.start,
+                            });
+                            continue;
+                        }
+                        _ => (),
+                    }
+                }
+                embedded.source.push(c);
+                continue;
+            }
+            if comment_depth > 0 {
+                if line[col..].starts_with("/*") {
+                    comment_depth += 1;
+                    skip_until = col + 2;
+                } else if line[col..].starts_with("*/") {
+                    comment_depth -= 1;
+                    skip_until = col + 2;
+                }
+                continue;
+            }
+            if !is_in_string && (line[col..].starts_with("//") || line[col..].starts_with("/*")) {
+                if !current_token_value.is_empty() {
+                    tokens.push(new_token(
+                        current_token_value,
+                        token_start,
+                        (row + 1, col + 1),
+                        is_in_string,
+                    ));
+                    current_token_value = String::from("");
+                    is_new_token = true;
+                }
+                if line[col..].starts_with("//") {
+                    break;
+                }
+                comment_depth = 1;
+                comment_start = (row + 1, col + 1);
+                skip_until = col + 2;
+                continue;
+            }
+            if c == '\\' && is_in_string {
+                match escape_sequence(&line[col + 1..]) {
+                    Some((escaped, length)) => {
+                        current_token_value.push(escaped);
+                        skip_until = col + 1 + length;
+                    }
+                    None => {
+                        let sequence = match line[col + 1..].chars().next() {
+                            Some('u') => {
+                                &line[col..col + 2 + unicode_escape_length(&line[col + 2..])]
+                            }
+                            Some(c) => &line[col..col + 1 + c.len_utf8()],
+                            None => "\\",
+                        };
+                        diagnostics.report(CompilerError::new(
+                            ErrorTypes::UnexpectedSymbol,
+                            format!("unknown escape sequence '{}' in string", sequence),
+                            span((row + 1, col + 1), (row + 1, col + sequence.len() + 1)),
+                        ));
+                        skip_until = col + sequence.len();
+                    }
+                }
+                continue;
+            }
+            if is_in_string && (c == '{' || c == '}') {
+                if line[col + 1..].starts_with(c) {
+                    // `{{` and `}}` stand for a literal brace.
+                    skip_until = col + 2;
+                } else if c == '{' {
+                    if !current_token_value.is_empty() {
+                        string_parts.push(InterpolationPart::Text(current_token_value));
+                        current_token_value = String::from("");
+                    }
+                    interpolation = Some(Interpolation {
+                        source: String::new(),
+                        start: span((row + 1, col + 2), (row + 1, col + 2)),
+                        depth: 0,
+                        is_in_string: false,
+                        is_escaped: false,
+                    });
+                    continue;
+                }
+                current_token_value.push(c);
+                continue;
+            }
             if c.is_whitespace() && !is_in_string {
-                if !is_new_token && &current_token_value != "" {
-                    add_token(current_token_value, row + 1, col + 1, is_in_string);
+                if !is_new_token && !current_token_value.is_empty() {
+                    tokens.push(new_token(
+                        current_token_value,
+                        token_start,
+                        (row + 1, col + 1),
+                        is_in_string,
+                    ));
                     current_token_value = String::from("");
                     is_new_token = true;
                 }
                 continue;
             }
             if c == '"' {
-                if is_in_string {
-                    add_token(current_token_value, row + 1, col + 2, is_in_string);
+                if is_in_string && !string_parts.is_empty() {
+                    if !current_token_value.is_empty() {
+                        string_parts.push(InterpolationPart::Text(current_token_value));
+                    }
+                    tokens.push(Token {
+                        token: TokenTypes::Interpolation(InterpolationVariants::from_parts(
+                            std::mem::take(&mut string_parts),
+                        )),
+                        position: span(string_start, (row + 1, col + 2)),
+                    });
+                    is_in_string = false;
+                    current_token_value = String::from("");
+                    is_new_token = true;
+                } else if is_in_string {
+                    tokens.push(new_token(
+                        current_token_value,
+                        string_start,
+                        (row + 1, col + 2),
+                        is_in_string,
+                    ));
                     is_in_string = false;
                     current_token_value = String::from("");
                     is_new_token = true;
                 } else {
+                    if !current_token_value.is_empty() {
+                        tokens.push(new_token(
+                            current_token_value,
+                            token_start,
+                            (row + 1, col + 1),
+                            is_in_string,
+                        ));
+                        current_token_value = String::from("");
+                    }
                     is_in_string = true;
-                    string_start_row = row + 1;
-                    string_start_col = col + 2;
+                    string_start = (row + 1, col + 1);
                 }
                 continue;
             }
-            current_token_value.push(c);
-            is_new_token = false;
-            if !is_in_string {
-                if is_enum_token_variant(current_token_value.clone()) {
-                    add_token(current_token_value, row + 1, col + 2, is_in_string);
-                    current_token_value = String::from("");
-                    is_new_token = true;
-                } else if is_enum_token_variant(c.to_string()) {
-                    current_token_value.pop();
-                    add_token(current_token_value, row + 1, col + 1, is_in_string);
-                    add_token(c.to_string(), row + 1, col + 2, is_in_string);
+            if !is_in_string && !continues_number(&current_token_value, c) {
+                if let Some(symbol) = control_symbol(&line[col..]) {
+                    if !current_token_value.is_empty() {
+                        tokens.push(new_token(
+                            current_token_value,
+                            token_start,
+                            (row + 1, col + 1),
+                            is_in_string,
+                        ));
+                    }
+                    tokens.push(new_token(
+                        symbol.to_string(),
+                        (row + 1, col + 1),
+                        (row + 1, col + symbol.len() + 1),
+                        is_in_string,
+                    ));
+                    skip_until = col + symbol.len();
                     current_token_value = String::from("");
                     is_new_token = true;
+                    continue;
                 }
             }
+            if current_token_value.is_empty() {
+                token_start = (row + 1, col + 1);
+            }
+            current_token_value.push(c);
+            is_new_token = false;
         }
-        if !is_in_string && !is_new_token {
-            add_token(current_token_value, row + 1, line.len() + 1, is_in_string);
+        if let Some(embedded) = &mut interpolation {
+            embedded.source.push('\n');
+        } else if is_in_string {
+            // `lines` drops the line break, strings keep it.
+            current_token_value.push('\n');
+        } else if !is_new_token {
+            tokens.push(new_token(
+                current_token_value,
+                token_start,
+                (row + 1, line.len() + 1),
+                is_in_string,
+            ));
             current_token_value = String::from("");
             is_new_token = true;
         }
     }
     if is_in_string {
-        let current_position =
-            Position::new(file_name.to_string(), string_start_row, string_start_col);
-        ErrorHandler::compiler_err(
+        let current_position = span(string_start, (string_start.0, string_start.1 + 1));
+        diagnostics.report(CompilerError::new(
             ErrorTypes::UnclosedString,
             String::from("expected '\"', but found end of file"),
-            current_position,
-        )
-    } else {
-        Ok(tokens)
+            current_position.clone(),
+        ));
+        tokens.push(Token::invalid("\"", current_position));
+    } else if comment_depth > 0 {
+        let current_position = span(comment_start, (comment_start.0, comment_start.1 + 2));
+        diagnostics.report(CompilerError::new(
+            ErrorTypes::UnclosedComment,
+            String::from("expected '*/', but found end of file"),
+            current_position.clone(),
+        ));
+        tokens.push(Token::invalid("/*", current_position));
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ops::Number;
+
+    fn tokenize(source: &str) -> Vec<Token> {
+        let diagnostics = Diagnostics::new();
+        let tokens = tokenizer(BufReader::new(source.as_bytes()), "test", &diagnostics).unwrap();
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics.into_errors());
+        tokens
+    }
+
+    fn errors(source: &str) -> Vec<CompilerError> {
+        let diagnostics = Diagnostics::new();
+        tokenizer(BufReader::new(source.as_bytes()), "test", &diagnostics).unwrap();
+        diagnostics.into_errors()
+    }
+
+    fn controls(source: &str) -> Vec<ControlVariants> {
+        tokenize(source)
+            .into_iter()
+            .map(|token| match token.token {
+                TokenTypes::Control(control) => control,
+                token => panic!("expected a control token but found '{}'", token),
+            })
+            .collect()
+    }
+
+    const SPELLINGS: [(&str, ControlVariants); 29] = [
+        (",", ControlVariants::Comma),
+        (".", ControlVariants::Dot),
+        (";", ControlVariants::Semicolon),
+        ("(", ControlVariants::OpenParanthesis),
+        (")", ControlVariants::CloseParanthesis),
+        ("{", ControlVariants::OpenCurly),
+        ("}", ControlVariants::CloseCurly),
+        ("[", ControlVariants::OpenSquare),
+        ("]", ControlVariants::CloseSquare),
+        (":", ControlVariants::Colon),
+        ("?", ControlVariants::Questionmark),
+        ("<", ControlVariants::Less),
+        ("<=", ControlVariants::LessEqual),
+        (">", ControlVariants::Greater),
+        (">=", ControlVariants::GreaterEqual),
+        ("=", ControlVariants::Asign),
+        ("==", ControlVariants::Equal),
+        ("=>", ControlVariants::FatArrow),
+        ("||", ControlVariants::Or),
+        ("&&", ControlVariants::And),
+        ("|", ControlVariants::BitOr),
+        ("&", ControlVariants::BitAnd),
+        ("+", ControlVariants::Plus),
+        ("-", ControlVariants::Minus),
+        ("*", ControlVariants::Multiplikation),
+        ("**", ControlVariants::Power),
+        ("/", ControlVariants::Divide),
+        ("!", ControlVariants::Not),
+        ("!=", ControlVariants::NotEqual),
+    ];
+
+    #[test]
+    fn every_spelling_is_one_token() {
+        for (spelling, variant) in SPELLINGS {
+            assert_eq!(controls(spelling), vec![variant.clone()], "{}", spelling);
+            assert_eq!(variant.to_string(), spelling);
+        }
+    }
+
+    #[test]
+    fn every_spelling_between_names() {
+        for (spelling, variant) in SPELLINGS {
+            let tokens = tokenize(&format!("a{}b", spelling));
+            assert_eq!(tokens.len(), 3, "{}", spelling);
+            assert!(
+                matches!(&tokens[1].token, TokenTypes::Control(c) if *c == variant),
+                "{}",
+                spelling
+            );
+        }
+    }
+
+    #[test]
+    fn longest_operator_wins() {
+        use ControlVariants::*;
+        assert_eq!(controls("<=="), vec![LessEqual, Asign]);
+        assert_eq!(controls("==="), vec![Equal, Asign]);
+        assert_eq!(controls("!=="), vec![NotEqual, Asign]);
+        assert_eq!(controls("***"), vec![Power, Multiplikation]);
+        assert_eq!(controls("&&&"), vec![And, BitAnd]);
+        assert_eq!(controls("|||"), vec![Or, BitOr]);
+        assert_eq!(controls("<>"), vec![Less, Greater]);
+        assert_eq!(controls("= ="), vec![Asign, Asign]);
+    }
+
+    #[test]
+    fn keywords_only_match_whole_words() {
+        for name in [
+            "format", "iffy", "numbers", "ifelse", "constant", "_if", "if_", "str2",
+        ] {
+            let tokens = tokenize(name);
+            assert_eq!(tokens.len(), 1, "{}", name);
+            assert!(
+                matches!(&tokens[0].token, TokenTypes::Name(n) if n.to_string() == name),
+                "{}",
+                name
+            );
+        }
+        let tokens = tokenize("if(x)");
+        assert!(matches!(tokens[0].token, TokenTypes::Word(_)));
+        assert!(matches!(tokens[2].token, TokenTypes::Name(_)));
+    }
+
+    #[test]
+    fn invalid_names_are_rejected() {
+        for source in ["1abc", "a$b", "über"] {
+            assert!(!errors(source).is_empty(), "{}", source);
+        }
+        for source in [
+            r#"if (true) { print("\u41"); };"#,
+            r#"print("\u{12345678}", 1);"#,
+            r#"print("\u{zz} {1}");"#,
+        ] {
+            assert_eq!(errors(source).len(), 1, "{}", source);
+        }
+    }
+
+    #[test]
+    fn comments_are_skipped() {
+        let tokens = tokenize("a // b c\nd /* e\n/* f */ g\n*/ h/**/i//");
+        let names = tokens
+            .iter()
+            .map(|token| token.token.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(names, vec!["a", "d", "h", "i"]);
+        assert!(tokens[1].position.to_string().starts_with("test:2:"));
+        assert!(tokens[2].position.to_string().starts_with("test:4:"));
+    }
+
+    #[test]
+    fn comment_markers_stay_in_strings() {
+        let tokens = tokenize("\"// /*\" a");
+        assert_eq!(tokens.len(), 2);
+    }
+
+    #[test]
+    fn unclosed_comment_is_an_error() {
+        assert!(!errors("a /* /* */").is_empty());
+    }
+
+    fn string(source: &str) -> String {
+        match &tokenize(source)[..] {
+            [Token {
+                token: TokenTypes::String(str),
+                ..
+            }] => str.value().to_string(),
+            tokens => panic!("expected a single string but found {:?}", tokens),
+        }
+    }
+
+    #[test]
+    fn string_contents_are_never_symbols() {
+        for content in ["<=", "!", "if", "num", "true", "12", "a b"] {
+            assert_eq!(string(&format!("\"{}\"", content)), content);
+        }
+    }
+
+    #[test]
+    fn escape_sequences() {
+        assert_eq!(
+            string(r#""\"q\" \\ \n\t\r\0 \u{41}\u{1F600}""#),
+            "\"q\" \\ \n\t\r\0 A\u{1F600}"
+        );
+        for source in [
+            r#""\q""#,
+            r#""\u{}""#,
+            r#""\u{110000}""#,
+            r#""\u41""#,
+            "\"\\",
+        ] {
+            assert!(!errors(source).is_empty(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn positions_span_the_token() {
+        let span = |start, end| Position::span(String::from("test"), start, end);
+        let tokens = tokenize("int x <= 1_0;\nstr s = \"a\\\"\n b\";");
+        assert_eq!(tokens[0].position, span((1, 1), (1, 4)));
+        assert_eq!(tokens[2].position, span((1, 7), (1, 9)));
+        assert_eq!(tokens[3].position, span((1, 10), (1, 13)));
+        assert_eq!(tokens[8].position, span((2, 9), (3, 4)));
+    }
+
+    #[test]
+    fn columns_count_characters() {
+        let span = |start, end| Position::span(String::from("test"), start, end);
+        let tokens = tokenize("str s = \"äöü\"; print(yy);\nstr t = \"ü\n€{x}\";");
+        assert_eq!(tokens[3].position, span((1, 9), (1, 14)));
+        assert_eq!(tokens[7].position, span((1, 22), (1, 24)));
+        let interpolation = tokens.iter().rev().nth(1).unwrap();
+        assert_eq!(interpolation.position, span((2, 9), (3, 6)));
+        let error = &errors("str t = \"ü\n€{1__0}\";")[0];
+        assert!(error.to_string().ends_with("test:2:3"), "{}", error);
+    }
+
+    #[test]
+    fn strings_keep_line_breaks() {
+        assert_eq!(string("\"a\n  b\n\""), "a\n  b\n");
+    }
+
+    #[test]
+    fn interpolation_parts() {
+        let tokens = tokenize("x = \"a {{b}} {name}!{f(\"}\", {1})}\";");
+        let parts = match &tokens[2].token {
+            TokenTypes::Interpolation(interpolation) => interpolation.parts(),
+            token => panic!("expected an interpolated string but found '{}'", token),
+        };
+        assert_eq!(parts.len(), 4);
+        assert!(matches!(&parts[0], InterpolationPart::Text(text) if text == "a {b} "));
+        match &parts[1] {
+            InterpolationPart::Expression { tokens, position } => {
+                assert_eq!(tokens.len(), 1);
+                assert_eq!(position.to_string(), "test:1:15");
+                assert_eq!(
+                    tokens[0].position,
+                    Position::span(String::from("test"), (1, 15), (1, 19))
+                );
+            }
+            _ => panic!("expected an embedded expression"),
+        }
+        assert!(matches!(&parts[2], InterpolationPart::Text(text) if text == "!"));
+        match &parts[3] {
+            InterpolationPart::Expression { tokens, .. } => assert_eq!(tokens.len(), 8),
+            _ => panic!("expected an embedded expression"),
+        }
+        assert!(matches!(
+            tokens[3].token,
+            TokenTypes::Control(ControlVariants::Semicolon)
+        ));
+    }
+
+    #[test]
+    fn interpolation_errors_point_into_the_string() {
+        let error = &errors("\"ab {1a}\"")[0];
+        assert!(error.to_string().ends_with("test:1:6"), "{}", error);
+        assert!(!errors("\"{ }\"").is_empty());
+        assert!(!errors("\"{a\"").is_empty());
+    }
+
+    fn number(source: &str) -> Number {
+        match &tokenize(source)[..] {
+            [Token {
+                token: TokenTypes::Number(num),
+                ..
+            }] => num.value(),
+            tokens => panic!("expected a single number but found {:?}", tokens),
+        }
+    }
+
+    #[test]
+    fn number_literals() {
+        assert_eq!(number("2.75"), Number::Float(2.75));
+        assert_eq!(number("0xFF"), Number::Int(255));
+        assert_eq!(number("0xff_ff"), Number::Int(65535));
+        assert_eq!(number("0b1010"), Number::Int(10));
+        assert_eq!(number("0o17"), Number::Int(15));
+        assert_eq!(number("1_000_000"), Number::Int(1_000_000));
+        assert_eq!(number("1e10"), Number::Float(1e10));
+        assert_eq!(number("2.5E-3"), Number::Float(2.5e-3));
+        assert_eq!(number("1_0.0_1e+1_0"), Number::Float(10.01e10));
+        assert_eq!(number("007"), Number::Int(7));
+    }
+
+    #[test]
+    fn int_and_float_literals() {
+        assert_eq!(number("9223372036854775807"), Number::Int(i64::MAX as u64));
+        assert_eq!(number("9223372036854775808"), Number::Int(1 << 63));
+        assert_eq!(number("1.0"), Number::Float(1.0));
+        assert!(!errors("9223372036854775809").is_empty());
+    }
+
+    #[test]
+    fn malformed_number_literals() {
+        for source in [
+            "0x",
+            "1__0",
+            "1_",
+            "0b102",
+            "0o8",
+            "0x_1",
+            "1.",
+            "1.e5",
+            "1e",
+            "1e+",
+            "1ee5",
+            "1a",
+            "1e999",
+            "0x1_0000_0000_0000_0000",
+        ] {
+            assert!(!errors(source).is_empty(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn number_words_are_names() {
+        for name in ["inf", "NaN", "infinity", "nan", "e5"] {
+            assert!(
+                matches!(tokenize(name)[0].token, TokenTypes::Name(_)),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn numbers_next_to_operators() {
+        use ControlVariants::*;
+        let tokens = tokenize("1e-5-2.5+a.b");
+        assert_eq!(tokens.len(), 7);
+        assert!(matches!(tokens[1].token, TokenTypes::Control(Minus)));
+        assert!(matches!(tokens[3].token, TokenTypes::Control(Plus)));
+        assert!(matches!(tokens[5].token, TokenTypes::Control(Dot)));
+    }
+
+    #[test]
+    fn all_errors_are_collected_in_order() {
+        let errors = errors("\"{ }\" 1__0 ok\n\"a\\q\" 0b2\n/* open");
+        let positions: Vec<String> = errors
+            .iter()
+            .map(|error| error.to_string().rsplit(' ').next().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            positions,
+            ["test:1:3", "test:1:7", "test:2:3", "test:2:7", "test:3:1"]
+        );
     }
 }